@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Artifact {
+	// the split svg a layer was rendered from
+	Svg,
+	// the rgba8 pixels of a texture before compression
+	Png,
+}
+
+impl Artifact {
+	fn extension(self) -> &'static str {
+		match self {
+			Artifact::Svg => "svg",
+			Artifact::Png => "png",
+		}
+	}
+}
+
+// which debug sidecars get written for the outputs, they go to their own tree mirroring files/ so they never end up in the mod
+#[derive(Debug, Clone)]
+pub struct Artifacts {
+	pub emit: Vec<Artifact>,
+	pub files_root: PathBuf,
+	pub debug_root: PathBuf,
+}
+
+impl Artifacts {
+	pub fn new(args: &crate::cli::Args) -> Self {
+		Self {
+			emit: args.emit.clone(),
+			files_root: args.target.join("files"),
+			// next to the build cache and stage manifests, only meta.json and files/ get packed into the mod
+			debug_root: args.debug_dir.clone().unwrap_or_else(|| args.target.join(".preprocessor").join("debug")),
+		}
+	}
+	
+	// builds before the debug tree wrote the split svg and pixels of every layer next to its texture in files/ (0.svg and 0.png next to 0.tex),
+	// which ended up in the packed mod. removed once, the marker keeps it from walking files/ every build
+	pub fn remove_legacy(&self, target_root: &Path) -> Result<usize, crate::Error> {
+		fn walk(dir: &Path, count: &mut usize) -> Result<(), crate::Error> {
+			for entry in std::fs::read_dir(dir)? {
+				let path = entry?.path();
+				if path.is_dir() {
+					walk(&path, count)?;
+				} else if is_legacy(&path) {
+					std::fs::remove_file(&path)?;
+					*count += 1;
+				}
+			}
+			
+			Ok(())
+		}
+		
+		let marker = target_root.join(".preprocessor").join("legacy_sidecars_removed");
+		if marker.exists() {return Ok(0)}
+		
+		let mut count = 0;
+		if self.files_root.exists() {
+			walk(&self.files_root, &mut count)?;
+		}
+		
+		std::fs::create_dir_all(marker.parent().unwrap())?;
+		std::fs::write(marker, "")?;
+		Ok(count)
+	}
+	
+	// where the sidecar of an output in files/ goes
+	fn path(&self, file: &Path, artifact: Artifact) -> Option<PathBuf> {
+		let relative = file.strip_prefix(&self.files_root).ok()?;
+		Some(self.debug_root.join(relative).with_extension(artifact.extension()))
+	}
+	
+	// path of the sidecar if that kind gets emitted, creating its directory
+	fn emitted(&self, file: &Path, artifact: Artifact) -> Result<Option<PathBuf>, crate::Error> {
+		if !self.emit.contains(&artifact) {return Ok(None)}
+		let path = self.path(file, artifact).ok_or_else(|| format!("{file:?} is not in the files root"))?;
+		std::fs::create_dir_all(path.parent().unwrap())?;
+		Ok(Some(path))
+	}
+	
+	pub fn svg(&self, file: &Path, data: &str) -> Result<(), crate::Error> {
+		if let Some(path) = self.emitted(file, Artifact::Svg)? {
+			std::fs::write(path, data)?;
+		}
+		
+		Ok(())
+	}
+	
+	pub fn png(&self, file: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), crate::Error> {
+		if let Some(path) = self.emitted(file, Artifact::Png)? {
+			image::RgbaImage::from_raw(width, height, rgba.to_vec()).ok_or("Pixels don't match the image size")?.save(path)?;
+		}
+		
+		Ok(())
+	}
+	
+	// if every emitted sidecar of the output exists, so that cached outputs still get them after changing the policy or clearing the debug tree
+	pub fn exist(&self, file: &Path) -> bool {
+		self.emit.iter().all(|v| self.path(file, *v).is_none_or(|v| v.exists()))
+	}
+	
+	// removes every sidecar of an output, emitted or not, cleaning up directories left empty
+	pub fn remove(&self, file: &Path) -> Result<(), crate::Error> {
+		for artifact in [Artifact::Svg, Artifact::Png] {
+			let Some(path) = self.path(file, artifact) else {continue};
+			if path.exists() {
+				std::fs::remove_file(&path)?;
+			}
+			
+			let mut dir = path.parent();
+			while let Some(d) = dir {
+				if d == self.debug_root || std::fs::remove_dir(d).is_err() {break}
+				dir = d.parent();
+			}
+		}
+		
+		Ok(())
+	}
+}
+
+// only the layer numbered names render_svg used, so files merged in from raw/ are never touched
+fn is_legacy(path: &Path) -> bool {
+	let numbered = path.file_stem().and_then(|v| v.to_str()).is_some_and(|v| !v.is_empty() && v.bytes().all(|v| v.is_ascii_digit()));
+	numbered && path.extension().is_some_and(|v| v == "svg" || v == "png") && path.with_extension("tex").exists()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn legacy_sidecars() {
+		let target_root = std::env::temp_dir().join(format!("preprocessor-legacy-{}", std::process::id()));
+		_ = std::fs::remove_dir_all(&target_root);
+		let files_root = target_root.join("files");
+		let dir = files_root.join("ui/uld/button_hr1.tex");
+		std::fs::create_dir_all(&dir).unwrap();
+		for file in ["0.tex", "0.svg", "0.png", "1.svg"] {
+			std::fs::write(dir.join(file), "").unwrap();
+		}
+		// merged in from raw/
+		for file in ["ui/uld/logo_hr1.tex", "ui/uld/logo_hr1.png"] {
+			std::fs::write(files_root.join(file), "").unwrap();
+		}
+		
+		let artifacts = Artifacts{emit: Vec::new(), files_root: files_root.clone(), debug_root: target_root.join("debug")};
+		assert_eq!(artifacts.remove_legacy(&target_root).unwrap(), 2);
+		assert!(dir.join("0.tex").exists());
+		// not a sidecar of anything
+		assert!(dir.join("1.svg").exists());
+		assert!(files_root.join("ui/uld/logo_hr1.png").exists());
+		
+		// only once
+		std::fs::write(dir.join("0.png"), "").unwrap();
+		assert_eq!(artifacts.remove_legacy(&target_root).unwrap(), 0);
+		assert!(dir.join("0.png").exists());
+		
+		_ = std::fs::remove_dir_all(&target_root);
+	}
+}
@@ -0,0 +1,500 @@
+use rayon::{iter::{IndexedParallelIterator, ParallelIterator}, slice::ParallelSliceMut};
+
+// block compression encoders and decoders, plain cpu code so that it works anywhere without gpu or prebuilt libraries
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+	// endpoints from the bounding box, good enough while iterating on svgs
+	Fast,
+	// endpoints from the principal axis, refined a couple times
+	#[default]
+	Normal,
+	// same as normal, but refined until it stops improving
+	High,
+}
+
+impl Quality {
+	fn refinements(self) -> usize {
+		match self {
+			Quality::Fast => 0,
+			Quality::Normal => 2,
+			Quality::High => 8,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+	// rgb with 1 bit alpha, 8 bytes
+	Bc1,
+	// rgb with interpolated alpha, 16 bytes
+	Bc3,
+	// rgba using mode 6 only, 16 bytes
+	Bc7,
+}
+
+impl Block {
+	pub fn size(self) -> usize {
+		match self {
+			Block::Bc1 => 8,
+			Block::Bc3 | Block::Bc7 => 16,
+		}
+	}
+}
+
+// encodes rgba8 pixels, blocks going past the edge of the image repeat the edge pixels
+pub fn encode(block: Block, quality: Quality, width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+	let blocks_x = width.div_ceil(4);
+	let blocks_y = height.div_ceil(4);
+	let size = block.size();
+	let mut data = vec![0u8; blocks_x * blocks_y * size];
+	data.par_chunks_mut(blocks_x * size).enumerate().for_each(|(by, row)| {
+		for bx in 0..blocks_x {
+			let mut pixels = [[0u8; 4]; 16];
+			for (i, pixel) in pixels.iter_mut().enumerate() {
+				let x = (bx * 4 + i % 4).min(width - 1);
+				let y = (by * 4 + i / 4).min(height - 1);
+				let offset = (y * width + x) * 4;
+				pixel.copy_from_slice(&rgba[offset..offset + 4]);
+			}
+			
+			let out = &mut row[bx * size..(bx + 1) * size];
+			match block {
+				Block::Bc1 => out.copy_from_slice(&bc1(&pixels, quality)),
+				Block::Bc3 => {
+					out[..8].copy_from_slice(&bc4(&pixels.map(|v| v[3]), quality));
+					out[8..].copy_from_slice(&color_block(&pixels, quality, false));
+				}
+				Block::Bc7 => out.copy_from_slice(&bc7_mode6(&pixels, quality)),
+			}
+		}
+	});
+	
+	data
+}
+
+// decodes to rgba8, only the bc7 modes without partitions are supported (which covers everything encode writes)
+pub fn decode(block: Block, width: usize, height: usize, data: &[u8]) -> Result<Vec<u8>, crate::Error> {
+	let blocks_x = width.div_ceil(4);
+	let blocks_y = height.div_ceil(4);
+	let size = block.size();
+	if data.len() < blocks_x * blocks_y * size {
+		return Err(format!("{block:?} data for {width}x{height} should be {} bytes, but is {}", blocks_x * blocks_y * size, data.len()).into());
+	}
+	
+	let mut rgba = vec![0u8; width * height * 4];
+	for by in 0..blocks_y {
+		for bx in 0..blocks_x {
+			let input = &data[(by * blocks_x + bx) * size..(by * blocks_x + bx + 1) * size];
+			let pixels = match block {
+				Block::Bc1 => decode_color_block(input, true),
+				Block::Bc3 => {
+					let alpha = decode_bc4(&input[..8]);
+					let mut pixels = decode_color_block(&input[8..], false);
+					for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+						pixel[3] = alpha;
+					}
+					pixels
+				}
+				Block::Bc7 => decode_bc7(input)?,
+			};
+			
+			for (i, pixel) in pixels.iter().enumerate() {
+				let (x, y) = (bx * 4 + i % 4, by * 4 + i / 4);
+				if x < width && y < height {
+					let offset = (y * width + x) * 4;
+					rgba[offset..offset + 4].copy_from_slice(pixel);
+				}
+			}
+		}
+	}
+	
+	Ok(rgba)
+}
+
+fn distance<const N: usize>(a: &[f32; N], b: &[f32; N]) -> f32 {
+	a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+fn nearest<const N: usize>(pixel: &[f32; N], palette: &[[f32; N]]) -> usize {
+	(0..palette.len()).min_by(|a, b| distance(pixel, &palette[*a]).total_cmp(&distance(pixel, &palette[*b]))).unwrap_or(0)
+}
+
+// fits two endpoints so that interpolating between them with the given weights is as close to the pixels as possible
+fn fit<const N: usize>(pixels: &[[f32; N]], weights: &[f32], quality: Quality) -> ([f32; N], [f32; N]) {
+	let mut e0 = [f32::MAX; N];
+	let mut e1 = [f32::MIN; N];
+	if quality == Quality::Fast {
+		for pixel in pixels {
+			for c in 0..N {
+				e0[c] = e0[c].min(pixel[c]);
+				e1[c] = e1[c].max(pixel[c]);
+			}
+		}
+	} else {
+		// principal axis through power iteration on the covariance
+		let mut mean = [0.0; N];
+		for pixel in pixels {
+			for c in 0..N {mean[c] += pixel[c] / pixels.len() as f32}
+		}
+		
+		let mut covariance = [[0.0; N]; N];
+		for pixel in pixels {
+			for a in 0..N {
+				for b in 0..N {
+					covariance[a][b] += (pixel[a] - mean[a]) * (pixel[b] - mean[b]);
+				}
+			}
+		}
+		
+		let mut axis = [1.0; N];
+		for _ in 0..8 {
+			let mut next = [0.0; N];
+			for a in 0..N {
+				for b in 0..N {next[a] += covariance[a][b] * axis[b]}
+			}
+			
+			let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+			if length < f32::EPSILON {break}
+			axis = next.map(|v| v / length);
+		}
+		
+		let (mut min, mut max) = (f32::MAX, f32::MIN);
+		for pixel in pixels {
+			let t = (0..N).map(|c| (pixel[c] - mean[c]) * axis[c]).sum::<f32>();
+			min = min.min(t);
+			max = max.max(t);
+		}
+		
+		for c in 0..N {
+			e0[c] = (mean[c] + axis[c] * min).clamp(0.0, 255.0);
+			e1[c] = (mean[c] + axis[c] * max).clamp(0.0, 255.0);
+		}
+	}
+	
+	// least squares on the endpoints for the current index assignment
+	for _ in 0..quality.refinements() {
+		let palette = weights.iter().map(|w| std::array::from_fn(|c| e0[c] + (e1[c] - e0[c]) * w)).collect::<Vec<[f32; N]>>();
+		let (mut a, mut b, mut c) = (0.0, 0.0, 0.0);
+		let (mut d0, mut d1) = ([0.0; N], [0.0; N]);
+		for pixel in pixels {
+			let w = weights[nearest(pixel, &palette)];
+			a += (1.0 - w) * (1.0 - w);
+			b += (1.0 - w) * w;
+			c += w * w;
+			for i in 0..N {
+				d0[i] += (1.0 - w) * pixel[i];
+				d1[i] += w * pixel[i];
+			}
+		}
+		
+		let det = a * c - b * b;
+		if det.abs() < f32::EPSILON {break}
+		
+		let new0 = std::array::from_fn(|i| ((c * d0[i] - b * d1[i]) / det).clamp(0.0, 255.0));
+		let new1 = std::array::from_fn(|i| ((a * d1[i] - b * d0[i]) / det).clamp(0.0, 255.0));
+		if distance(&new0, &e0) + distance(&new1, &e1) < 0.01 {break}
+		(e0, e1) = (new0, new1);
+	}
+	
+	(e0, e1)
+}
+
+fn to_565(color: &[f32; 3]) -> u16 {
+	let r = (color[0] * 31.0 / 255.0).round() as u16;
+	let g = (color[1] * 63.0 / 255.0).round() as u16;
+	let b = (color[2] * 31.0 / 255.0).round() as u16;
+	(r << 11) | (g << 5) | b
+}
+
+fn from_565(color: u16) -> [f32; 3] {
+	let r = (color >> 11) & 31;
+	let g = (color >> 5) & 63;
+	let b = color & 31;
+	[((r << 3) | (r >> 2)) as f32, ((g << 2) | (g >> 4)) as f32, ((b << 3) | (b >> 2)) as f32]
+}
+
+fn bc1(pixels: &[[u8; 4]; 16], quality: Quality) -> [u8; 8] {
+	color_block(pixels, quality, pixels.iter().any(|v| v[3] < 128))
+}
+
+// the bc1 color block, also used by bc3 which always uses 4 colors and ignores the 1 bit alpha
+fn color_block(pixels: &[[u8; 4]; 16], quality: Quality, punch_through: bool) -> [u8; 8] {
+	let colors = pixels.map(|v| [v[0] as f32, v[1] as f32, v[2] as f32]);
+	// color doesn't matter for transparent pixels
+	let visible = if punch_through {
+		pixels.iter().zip(&colors).filter(|(v, _)| v[3] >= 128).map(|(_, c)| *c).collect::<Vec<_>>()
+	} else {
+		pixels.iter().zip(&colors).filter(|(v, _)| v[3] > 0).map(|(_, c)| *c).collect::<Vec<_>>()
+	};
+	
+	let mut out = [0u8; 8];
+	if visible.is_empty() {
+		// c0 <= c1 with every index 3 is fully transparent in bc1, and irrelevant for bc3
+		out[4..].copy_from_slice(&if punch_through {[0xFF; 4]} else {[0; 4]});
+		return out;
+	}
+	
+	let weights: &[f32] = if punch_through {&[0.0, 0.5, 1.0]} else {&[0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]};
+	let (e0, e1) = fit(&visible, weights, quality);
+	let (mut c0, mut c1) = (to_565(&e0), to_565(&e1));
+	// the order of the endpoints decides between 4 colors and 3 colors with transparency
+	if (punch_through && c0 > c1) || (!punch_through && c0 < c1) {
+		(c0, c1) = (c1, c0);
+	}
+	
+	let (p0, p1) = (from_565(c0), from_565(c1));
+	let palette = if punch_through || c0 == c1 {
+		vec![p0, p1, std::array::from_fn(|c| (p0[c] + p1[c]) / 2.0)]
+	} else {
+		vec![p0, p1, std::array::from_fn(|c| (p0[c] * 2.0 + p1[c]) / 3.0), std::array::from_fn(|c| (p0[c] + p1[c] * 2.0) / 3.0)]
+	};
+	
+	let mut indices = 0u32;
+	for (i, (pixel, color)) in pixels.iter().zip(&colors).enumerate() {
+		let index = if punch_through && pixel[3] < 128 {3} else {nearest(color, &palette) as u32};
+		indices |= index << (i * 2);
+	}
+	
+	out[0..2].copy_from_slice(&c0.to_le_bytes());
+	out[2..4].copy_from_slice(&c1.to_le_bytes());
+	out[4..8].copy_from_slice(&indices.to_le_bytes());
+	out
+}
+
+fn decode_color_block(input: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+	let c0 = u16::from_le_bytes([input[0], input[1]]);
+	let c1 = u16::from_le_bytes([input[2], input[3]]);
+	let indices = u32::from_le_bytes([input[4], input[5], input[6], input[7]]);
+	let (p0, p1) = (from_565(c0), from_565(c1));
+	let color = |f: fn(f32, f32) -> f32| -> [u8; 4] {[f(p0[0], p1[0]) as u8, f(p0[1], p1[1]) as u8, f(p0[2], p1[2]) as u8, 255]};
+	let palette = if c0 > c1 || !punch_through {
+		[color(|a, _| a), color(|_, b| b), color(|a, b| ((a * 2.0 + b) / 3.0).round()), color(|a, b| ((a + b * 2.0) / 3.0).round())]
+	} else {
+		[color(|a, _| a), color(|_, b| b), color(|a, b| ((a + b) / 2.0).round()), [0; 4]]
+	};
+	
+	std::array::from_fn(|i| palette[((indices >> (i * 2)) & 3) as usize])
+}
+
+fn decode_bc4(input: &[u8]) -> [u8; 16] {
+	let (a0, a1) = (input[0] as u32, input[1] as u32);
+	let palette = if a0 > a1 {
+		[a0, a1, (6 * a0 + a1) / 7, (5 * a0 + 2 * a1) / 7, (4 * a0 + 3 * a1) / 7, (3 * a0 + 4 * a1) / 7, (2 * a0 + 5 * a1) / 7, (a0 + 6 * a1) / 7]
+	} else {
+		[a0, a1, (4 * a0 + a1) / 5, (3 * a0 + 2 * a1) / 5, (2 * a0 + 3 * a1) / 5, (a0 + 4 * a1) / 5, 0, 255]
+	};
+	
+	let mut bytes = [0u8; 8];
+	bytes[..6].copy_from_slice(&input[2..8]);
+	let indices = u64::from_le_bytes(bytes);
+	std::array::from_fn(|i| palette[((indices >> (i * 3)) & 7) as usize] as u8)
+}
+
+// the alpha block of bc3, tries both the 8 value mode and the 6 value mode with exact 0 and 255
+fn bc4(alpha: &[u8; 16], quality: Quality) -> [u8; 8] {
+	let values = alpha.map(|v| [v as f32]);
+	let mut best = ([0u8; 8], f32::MAX);
+	for extremes in [false, true] {
+		let fitted = values.iter().filter(|v| !extremes || (v[0] > 0.0 && v[0] < 255.0)).copied().collect::<Vec<_>>();
+		let (a0, a1) = if fitted.is_empty() {
+			(0, 0)
+		} else {
+			let weights = if extremes {[0.0, 0.2, 0.4, 0.6, 0.8, 1.0].as_slice()} else {[0.0, 1.0 / 7.0, 2.0 / 7.0, 3.0 / 7.0, 4.0 / 7.0, 5.0 / 7.0, 6.0 / 7.0, 1.0].as_slice()};
+			let (e0, e1) = fit(&fitted, weights, quality);
+			(e0[0].round() as u8, e1[0].round() as u8)
+		};
+		
+		// a0 > a1 is the 8 value mode, the other way around the 6 value one
+		let (a0, a1) = if extremes == (a0 > a1) {(a1, a0)} else {(a0, a1)};
+		let (f0, f1) = (a0 as f32, a1 as f32);
+		let palette = if a0 > a1 {
+			[f0, f1, (6.0 * f0 + f1) / 7.0, (5.0 * f0 + 2.0 * f1) / 7.0, (4.0 * f0 + 3.0 * f1) / 7.0, (3.0 * f0 + 4.0 * f1) / 7.0, (2.0 * f0 + 5.0 * f1) / 7.0, (f0 + 6.0 * f1) / 7.0]
+		} else {
+			[f0, f1, (4.0 * f0 + f1) / 5.0, (3.0 * f0 + 2.0 * f1) / 5.0, (2.0 * f0 + 3.0 * f1) / 5.0, (f0 + 4.0 * f1) / 5.0, 0.0, 255.0]
+		}.map(|v| [v.trunc()]);
+		
+		let mut indices = 0u64;
+		let mut error = 0.0;
+		for (i, value) in values.iter().enumerate() {
+			let index = nearest(value, &palette);
+			error += distance(value, &palette[index]);
+			indices |= (index as u64) << (i * 3);
+		}
+		
+		if error < best.1 {
+			let mut out = [0u8; 8];
+			out[0] = a0;
+			out[1] = a1;
+			out[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+			best = (out, error);
+		}
+	}
+	
+	best.0
+}
+
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+
+// reads bits in the same order BitWriter writes them
+struct BitReader<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl BitReader<'_> {
+	fn read(&mut self, bits: usize) -> u32 {
+		let mut value = 0;
+		for i in 0..bits {
+			if self.data[(self.position + i) / 8] & (1 << ((self.position + i) % 8)) != 0 {
+				value |= 1 << i;
+			}
+		}
+		
+		self.position += bits;
+		value
+	}
+	
+	// the index of the first pixel is one bit short, its top bit is always 0
+	fn indices(&mut self, bits: usize) -> [usize; 16] {
+		std::array::from_fn(|i| self.read(if i == 0 {bits - 1} else {bits}) as usize)
+	}
+}
+
+// scales an endpoint of the given bit count up to 8 bits, repeating the top bits in the bottom ones
+fn expand(value: u32, bits: usize) -> u32 {
+	(value << (8 - bits)) | (value >> (2 * bits - 8))
+}
+
+fn decode_bc7(input: &[u8]) -> Result<[[u8; 4]; 16], crate::Error> {
+	let mode = input[0].trailing_zeros();
+	let mut reader = BitReader{data: input, position: mode as usize + 1};
+	let interpolate = |e0: u32, e1: u32, w: u32| (((64 - w) * e0 + w * e1 + 32) >> 6) as u8;
+	
+	match mode {
+		// single subset, 7 bit rgba endpoints with unique p-bits
+		6 => {
+			let mut endpoints = [[0u32; 2]; 4];
+			for channel in &mut endpoints {
+				*channel = [reader.read(7), reader.read(7)];
+			}
+			let (p0, p1) = (reader.read(1), reader.read(1));
+			let endpoints = endpoints.map(|[e0, e1]| [(e0 << 1) | p0, (e1 << 1) | p1]);
+			let indices = reader.indices(4);
+			Ok(indices.map(|i| endpoints.map(|[e0, e1]| interpolate(e0, e1, BC7_WEIGHTS[i]))))
+		}
+		
+		// single subset with separate color and alpha indices, and a channel rotated into the alpha
+		4 | 5 => {
+			let rotation = reader.read(2);
+			let index_mode = if mode == 4 {reader.read(1)} else {0};
+			let (color_bits, alpha_bits) = if mode == 4 {(5, 6)} else {(7, 8)};
+			let mut endpoints = [[0u32; 2]; 4];
+			for (c, channel) in endpoints.iter_mut().enumerate() {
+				let bits = if c == 3 {alpha_bits} else {color_bits};
+				*channel = [expand(reader.read(bits), bits), expand(reader.read(bits), bits)];
+			}
+			
+			let first = reader.indices(2);
+			let second = if mode == 4 {reader.indices(3)} else {reader.indices(2)};
+			let weights = |indices: [usize; 16], bits: usize| indices.map(|i| if bits == 2 {BC7_WEIGHTS_2[i]} else {BC7_WEIGHTS_3[i]});
+			let second_bits = if mode == 4 {3} else {2};
+			let (color, alpha) = if index_mode == 0 {
+				(weights(first, 2), weights(second, second_bits))
+			} else {
+				(weights(second, second_bits), weights(first, 2))
+			};
+			
+			Ok(std::array::from_fn(|i| {
+				let mut pixel = [0u8; 4];
+				for c in 0..3 {
+					pixel[c] = interpolate(endpoints[c][0], endpoints[c][1], color[i]);
+				}
+				pixel[3] = interpolate(endpoints[3][0], endpoints[3][1], alpha[i]);
+				if rotation > 0 {
+					pixel.swap(rotation as usize - 1, 3);
+				}
+				pixel
+			}))
+		}
+		
+		// reserved, decodes to transparent black
+		8 => Ok([[0; 4]; 16]),
+		
+		_ => Err(format!("BC7 mode {mode} uses partitions, which aren't supported").into()),
+	}
+}
+
+// writes bits starting at the least significant bit of the first byte
+struct BitWriter {
+	data: [u8; 16],
+	position: usize,
+}
+
+impl BitWriter {
+	fn write(&mut self, value: u32, bits: usize) {
+		for i in 0..bits {
+			if value & (1 << i) != 0 {
+				self.data[(self.position + i) / 8] |= 1 << ((self.position + i) % 8);
+			}
+		}
+		
+		self.position += bits;
+	}
+}
+
+// 7 bit endpoints with a shared lowest bit (p-bit), picks the p-bit that gets closest to the 8 bit endpoint
+fn quantize_bc7(endpoint: &[f32; 4]) -> ([u8; 4], u8) {
+	let mut best = ([0u8; 4], 0, f32::MAX);
+	for p in 0..2u8 {
+		let values = endpoint.map(|v| (((v - p as f32) / 2.0).round().clamp(0.0, 127.0)) as u8);
+		let error = (0..4).map(|c| {
+			let d = ((values[c] << 1) | p) as f32 - endpoint[c];
+			d * d
+		}).sum::<f32>();
+		
+		if error < best.2 {
+			best = (values, p, error);
+		}
+	}
+	
+	(best.0, best.1)
+}
+
+fn bc7_mode6(pixels: &[[u8; 4]; 16], quality: Quality) -> [u8; 16] {
+	let values = pixels.map(|v| v.map(|c| c as f32));
+	let weights = BC7_WEIGHTS.map(|v| v as f32 / 64.0);
+	let (e0, e1) = fit(&values, &weights, quality);
+	let (mut q0, mut p0) = quantize_bc7(&e0);
+	let (mut q1, mut p1) = quantize_bc7(&e1);
+	
+	let palette = |q0: &[u8; 4], p0: u8, q1: &[u8; 4], p1: u8| -> Vec<[f32; 4]> {
+		let full0 = q0.map(|v| ((v << 1) | p0) as u32);
+		let full1 = q1.map(|v| ((v << 1) | p1) as u32);
+		BC7_WEIGHTS.iter().map(|w| std::array::from_fn(|c| (((64 - w) * full0[c] + w * full1[c] + 32) >> 6) as f32)).collect()
+	};
+	
+	let mut indices = values.map(|v| nearest(&v, &palette(&q0, p0, &q1, p1)));
+	// the first index has its top bit left out, so it has to be in the lower half
+	if indices[0] >= 8 {
+		(q0, p0, q1, p1) = (q1, p1, q0, p0);
+		indices = indices.map(|v| 15 - v);
+	}
+	
+	let mut writer = BitWriter{data: [0; 16], position: 0};
+	writer.write(1 << 6, 7);
+	for c in 0..4 {
+		writer.write(q0[c] as u32, 7);
+		writer.write(q1[c] as u32, 7);
+	}
+	writer.write(p0 as u32, 1);
+	writer.write(p1 as u32, 1);
+	for (i, index) in indices.iter().enumerate() {
+		writer.write(*index as u32, if i == 0 {3} else {4});
+	}
+	
+	writer.data
+}
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+use serde::{Deserialize, Serialize};
+
+// bump this whenever rendering changes in a way that isn't reflected in the hashed input
+pub const RENDER_VERSION: &str = "1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+	pub hash: String,
+	// files (relative to the files root) created from this entry
+	pub files: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+	pub entries: HashMap<String, Entry>,
+}
+
+impl Cache {
+	fn path(target_root: &Path) -> PathBuf {
+		target_root.join(".preprocessor").join("render_cache.json")
+	}
+	
+	pub fn load(target_root: &Path) -> Cache {
+		std::fs::read(Self::path(target_root)).ok()
+			.and_then(|v| serde_json::from_slice(&v).ok())
+			.unwrap_or_default()
+	}
+	
+	pub fn save(&self, target_root: &Path) -> Result<(), crate::Error> {
+		let path = Self::path(target_root);
+		_ = std::fs::create_dir_all(path.parent().unwrap());
+		std::fs::write(path, serde_json::to_vec(self)?)?;
+		
+		Ok(())
+	}
+	
+	// an entry is only fresh if it was created from the exact same input and none of its files got removed
+	pub fn is_fresh(&self, files_root: &Path, key: &str, hash: &str) -> bool {
+		self.entries.get(key).is_some_and(|v| v.hash == hash && v.files.iter().all(|f| files_root.join(f).exists()))
+	}
+	
+	// carries over the entries of a previous run that this one didn't create, so a later run still knows to prune their files
+	pub fn keep(&mut self, old: &Cache) {
+		for (key, entry) in &old.entries {
+			self.entries.entry(key.clone()).or_insert_with(|| entry.clone());
+		}
+	}
+	
+	// removes every file that was created in a previous run but isn't anymore, such as outputs of deleted svgs or layers, along with their debug sidecars
+	pub fn prune(&self, files_root: &Path, new: &Cache, artifacts: &crate::artifacts::Artifacts) -> Result<usize, crate::Error> {
+		let mut count = 0;
+		for (key, entry) in &self.entries {
+			let new_files = new.entries.get(key).map(|v| &v.files);
+			for file in &entry.files {
+				if new_files.is_some_and(|v| v.contains(file)) {continue}
+				
+				let path = files_root.join(file);
+				if path.exists() {
+					std::fs::remove_file(&path)?;
+					count += 1;
+				}
+				
+				artifacts.remove(&path)?;
+				
+				// clean up directories left empty
+				let mut dir = path.parent();
+				while let Some(d) = dir {
+					if d == files_root || std::fs::remove_dir(d).is_err() {break}
+					dir = d.parent();
+				}
+			}
+		}
+		
+		Ok(count)
+	}
+}
+
+pub fn hash(parts: &[&[u8]]) -> String {
+	let mut hasher = blake3::Hasher::new();
+	for part in parts {
+		// length prefix so that moving bytes between parts changes the hash
+		hasher.update(&(part.len() as u64).to_le_bytes());
+		hasher.update(part);
+	}
+	
+	hasher.finalize().to_hex().to_string()
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Parser)]
+#[command(about = "Builds Frost UI from its vectors, icons and meta.yaml")]
+pub struct Args {
+	#[command(subcommand)]
+	pub command: Command,
+	
+	/// Root directory containing the svg files
+	#[arg(long, global = true, default_value = "./vectors")]
+	pub svg: PathBuf,
+	
+	/// Root directory of the svgs shared between vectors, used through <use href="lib:<path without .svg>">
+	#[arg(long, global = true, default_value = "./vector_lib")]
+	pub lib: PathBuf,
+	
+	/// Root directory of the mod, files are written to <target>/files
+	#[arg(long, global = true, default_value = "./")]
+	pub target: PathBuf,
+	
+	/// The meta.yaml to create meta.json from
+	#[arg(long, global = true, default_value = "./meta.yaml")]
+	pub meta: PathBuf,
+	
+	/// The icon families the icons stage creates from the icons of the game
+	#[arg(long, global = true, default_value = "./icons.yaml")]
+	pub icons: PathBuf,
+	
+	/// Directory of extracted game files (ui/icon/062000/062001_hr1.tex) for the icons and uld stages to use instead of the installed game
+	#[arg(long, global = true)]
+	pub game: Option<PathBuf>,
+	
+	/// Directory of static files that get copied into the mod as is
+	#[arg(long, global = true, default_value = "./raw")]
+	pub merge: PathBuf,
+	
+	/// Ignore the build cache and render everything again
+	#[arg(long, global = true)]
+	pub no_cache: bool,
+	
+	/// Keep running after the render, merge, meta or all command and rebuild the stages it runs when the svg root, merge directory or meta.yaml change
+	#[arg(long, global = true)]
+	pub watch: bool,
+	
+	/// Directory the option preview images of meta.yaml are written to
+	#[arg(long, global = true, default_value = "./assets")]
+	pub assets: PathBuf,
+	
+	/// Which outputs get block compressed, everything stays uncompressed if the file doesn't exist
+	#[arg(long, global = true, default_value = "./textures.yaml")]
+	pub textures: PathBuf,
+	
+	/// Overrides the compression quality of the textures file
+	#[arg(long, global = true)]
+	pub tex_quality: Option<crate::bc::Quality>,
+	
+	/// Overrides the supersampling of the textures file, svgs render at this many times their size and get scaled down
+	#[arg(long, global = true)]
+	pub supersample: Option<u32>,
+	
+	/// Also render a half scale texture for every _hr1 texture, for the standard texture resolution setting
+	#[arg(long, global = true)]
+	pub low_res: bool,
+	
+	/// Debug sidecars to write for every output, comma separated (svg, png)
+	#[arg(long, global = true, value_delimiter = ',')]
+	pub emit: Vec<crate::artifacts::Artifact>,
+	
+	/// Where debug sidecars are written, mirroring the files directory [default: <target>/.preprocessor/debug]
+	#[arg(long, global = true)]
+	pub debug_dir: Option<PathBuf>,
+	
+	/// Run the remaining stages even after a stage reported errors
+	#[arg(long, global = true)]
+	pub keep_going: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, clap::Subcommand)]
+pub enum Command {
+	/// Split and render all svgs
+	Render,
+	/// Generate the icon families of the icons file
+	Icons,
+	/// Patch game uld files
+	Uld,
+	/// Create meta.json from meta.yaml and the files of previous stages
+	Meta,
+	/// Copy static files from the merge directory
+	Merge,
+	/// Run every stage
+	All,
+	/// Check the layer labels of all svgs against the layer structure and meta.yaml
+	Lint,
+	/// Report shapes whose colors, outlines or corners deviate from the guidelines in info.md
+	Guidelines,
+	/// Render the option preview images meta.yaml references into the assets directory
+	Previews,
+	/// Composite every texture of the mod with the option values of a preset into plain png and tex files
+	Flatten {
+		/// Name of the preset in meta.yaml
+		preset: String,
+		
+		/// Directory to write the textures to
+		#[arg(long)]
+		out: PathBuf,
+	},
+	/// Render a contact sheet of key textures (windows, buttons, gauges, ...) for every preset in meta.yaml
+	Sheets {
+		/// Only render the sheet of this preset
+		#[arg(long)]
+		preset: Option<String>,
+		
+		/// Directory to write the sheets to, named after their preset
+		#[arg(long)]
+		out: PathBuf,
+	},
+}
@@ -0,0 +1,86 @@
+// flattening the layers of a composite texture ourselves, for images that show what aetherment creates at apply time
+use aetherment::modman::composite::tex::Blend;
+
+// a layer in straight rgba8, the color its color modifiers multiply it with and how it blends with the layers below
+pub struct Layer<'a> {
+	pub rgba: &'a [u8],
+	pub color: [f32; 4],
+	pub blend: Blend,
+}
+
+// a color option value, grayscale, rgb or rgba
+pub fn rgba(color: &[f32]) -> [f32; 4] {
+	match *color {
+		[v] => [v, v, v, 1.0],
+		[r, g, b] => [r, g, b, 1.0],
+		[r, g, b, a] => [r, g, b, a],
+		_ => [1.0; 4],
+	}
+}
+
+// a color option used as a single value, like the cull point of a mask or an opacity
+// single value options are opacities or grayscale, either way their value is the last one
+pub fn factor(color: &[f32]) -> Option<f32> {
+	color.last().copied()
+}
+
+// for layers with more than one color modifier
+pub fn multiply(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+	[a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+// culls the layer where the mask is darker than the cull point, like the alpha mask modifier
+pub fn mask(rgba: &mut [u8], mask: &[u8], cull_point: f32) {
+	for (pixel, mask) in rgba.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+		if (mask[0] as f32 / 255.0) < cull_point {
+			pixel[3] = 0;
+		}
+	}
+}
+
+// the color of a channel where the source covers the backdrop, before the alpha of either is considered
+fn mix(blend: &Blend, b: f32, s: f32) -> f32 {
+	let hard_light = |b: f32, s: f32| if s <= 0.5 {b * 2.0 * s} else {let s = 2.0 * s - 1.0; b + s - b * s};
+	match blend {
+		Blend::Normal => s,
+		Blend::Multiply => b * s,
+		Blend::Screen => b + s - b * s,
+		Blend::Overlay => hard_light(s, b),
+		Blend::HardLight => hard_light(b, s),
+		Blend::SoftLight => if s <= 0.5 {
+			b - (1.0 - 2.0 * s) * b * (1.0 - b)
+		} else {
+			let d = if b <= 0.25 {((16.0 * b - 12.0) * b + 4.0) * b} else {b.sqrt()};
+			b + (2.0 * s - 1.0) * (d - b)
+		}
+		Blend::Add => (b + s).min(1.0),
+		Blend::Subtract => (b - s).max(0.0),
+		Blend::Difference => (b - s).abs(),
+		Blend::Darken => b.min(s),
+		Blend::Lighten => b.max(s),
+	}
+}
+
+// draws every layer over the previous one, the first one being at the bottom
+pub fn flatten(width: u32, height: u32, layers: &[Layer]) -> Vec<u8> {
+	let mut out = vec![0.0f32; (width * height * 4) as usize];
+	for layer in layers {
+		let color = layer.color;
+		for (dst, src) in out.chunks_exact_mut(4).zip(layer.rgba.chunks_exact(4)) {
+			let sa = src[3] as f32 / 255.0 * color[3];
+			if sa <= 0.0 {continue}
+			
+			let da = dst[3];
+			let a = sa + da * (1.0 - sa);
+			for c in 0..3 {
+				let sc = src[c] as f32 / 255.0 * color[c];
+				// blending only applies where there's something to blend with
+				let sc = (1.0 - da) * sc + da * mix(&layer.blend, dst[c], sc);
+				dst[c] = (sc * sa + dst[c] * da * (1.0 - sa)) / a;
+			}
+			dst[3] = a;
+		}
+	}
+	
+	out.into_iter().map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8).collect()
+}
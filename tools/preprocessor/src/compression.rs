@@ -0,0 +1,114 @@
+use serde::Deserialize;
+use crate::{bc, mips, tex::Format};
+
+// how a single texture gets saved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Settings {
+	pub format: Format,
+	pub quality: bc::Quality,
+	// filter to create the mip chain with, only the full size level if none
+	pub mips: Option<mips::Filter>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+	// game path prefix the rule applies to, the longest matching prefix wins
+	pub prefix: String,
+	#[serde(default)]
+	pub format: Format,
+	#[serde(default)]
+	pub mips: Option<mips::Filter>,
+	// overrides the supersampling of the rules for this prefix
+	#[serde(default)]
+	pub supersample: Option<u32>,
+	// also apply to the layers aetherment composites at apply time (.comp files),
+	// off by default since color modifiers tint them and make compression artifacts a lot more obvious
+	#[serde(default)]
+	pub layers: bool,
+}
+
+// which outputs may be compressed or get mips, and how they get rendered, read from textures.yaml
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rules {
+	#[serde(default)]
+	pub quality: bc::Quality,
+	// also render a half scale texture for every _hr1 texture, for the standard texture resolution setting
+	#[serde(default)]
+	pub low_res: bool,
+	// svgs render at this many times their size and get scaled down, smoothing out edges antialiasing alone leaves jagged
+	#[serde(default = "default_supersample")]
+	pub supersample: u32,
+	#[serde(default)]
+	pub rules: Vec<Rule>,
+}
+
+fn default_supersample() -> u32 {1}
+
+impl Default for Rules {
+	fn default() -> Self {
+		Self {
+			quality: bc::Quality::default(),
+			low_res: false,
+			supersample: default_supersample(),
+			rules: Vec::new(),
+		}
+	}
+}
+
+impl Rules {
+	// no file means everything stays uncompressed, the cli options override the file
+	pub fn load(args: &crate::cli::Args) -> Result<Self, crate::Error> {
+		let mut rules = if args.textures.exists() {
+			serde_yaml::from_slice::<Rules>(&std::fs::read(&args.textures)?)?
+		} else {
+			Rules::default()
+		};
+		
+		if let Some(quality) = args.tex_quality {
+			rules.quality = quality;
+		}
+		
+		if let Some(supersample) = args.supersample {
+			rules.supersample = supersample;
+		}
+		
+		rules.low_res |= args.low_res;
+		
+		Ok(rules)
+	}
+	
+	fn find(&self, game_path: &str) -> Option<&Rule> {
+		let game_path = game_path.to_ascii_lowercase();
+		self.rules.iter()
+			.filter(|v| game_path.starts_with(&v.prefix.to_ascii_lowercase()))
+			.max_by_key(|v| v.prefix.len())
+	}
+	
+	fn settings(&self, rule: Option<&Rule>) -> Settings {
+		Settings {
+			format: rule.map_or(Format::Bgra8, |v| v.format),
+			quality: self.quality,
+			mips: rule.and_then(|v| v.mips),
+		}
+	}
+	
+	// a texture the game loads as is
+	pub fn file(&self, game_path: &str) -> Settings {
+		self.settings(self.find(game_path))
+	}
+	
+	// a layer of a composite texture of the game path
+	pub fn layer(&self, game_path: &str) -> Settings {
+		self.settings(self.find(game_path).filter(|v| v.layers))
+	}
+	
+	pub fn supersample(&self, game_path: &str) -> u32 {
+		self.find(game_path).and_then(|v| v.supersample).unwrap_or(self.supersample).max(1)
+	}
+	
+	// the standard resolution game path of a _hr1 texture, if those get rendered
+	pub fn low_res_path(&self, game_path: &str) -> Option<String> {
+		if !self.low_res {return None}
+		game_path.strip_suffix("_hr1.tex").map(|v| format!("{v}.tex"))
+	}
+}
@@ -0,0 +1,111 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	Warning,
+	Error,
+}
+
+// an error or warning along with where it came from, can be returned as an error from anywhere and
+// will keep its context once it ends up in the diagnostics
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub source: Option<String>,
+	pub context: Vec<(&'static str, String)>,
+	pub message: String,
+}
+
+impl Diagnostic {
+	pub fn error(message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Error,
+			source: None,
+			context: Vec::new(),
+			message: message.into(),
+		}
+	}
+	
+	pub fn warning(message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Warning,
+			..Self::error(message)
+		}
+	}
+	
+	pub fn source(mut self, source: impl Into<String>) -> Self {
+		self.source = Some(source.into());
+		self
+	}
+	
+	// the svg layer, meta option, etc. the diagnostic belongs to
+	pub fn context(mut self, kind: &'static str, value: impl Into<String>) -> Self {
+		self.context.push((kind, value.into()));
+		self
+	}
+}
+
+impl std::fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)?;
+		for (kind, value) in &self.context {
+			write!(f, "\n\t\t{kind}: {value}")?;
+		}
+		
+		Ok(())
+	}
+}
+
+impl std::error::Error for Diagnostic {}
+
+// shared between all stages (and rayon threads), collects everything that went wrong to report it at the end
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+	list: Mutex<Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+	pub fn push(&self, diagnostic: Diagnostic) {
+		self.list.lock().unwrap().push(diagnostic);
+	}
+	
+	// adds any error, keeping its context if it already is a diagnostic
+	pub fn push_error(&self, source: impl Into<String>, err: crate::Error) {
+		let diagnostic = match err.downcast::<Diagnostic>() {
+			Ok(diagnostic) => *diagnostic,
+			Err(err) => Diagnostic::error(err.to_string()),
+		};
+		
+		self.push(if diagnostic.source.is_none() {diagnostic.source(source)} else {diagnostic});
+	}
+	
+	pub fn errors(&self) -> usize {
+		self.list.lock().unwrap().iter().filter(|v| v.severity == Severity::Error).count()
+	}
+	
+	// prints everything grouped by the file it came from
+	pub fn report(&self) {
+		let list = self.list.lock().unwrap();
+		if list.is_empty() {return}
+		
+		let mut grouped = BTreeMap::<String, Vec<&Diagnostic>>::new();
+		for diagnostic in list.iter() {
+			grouped.entry(diagnostic.source.clone().unwrap_or_else(|| "<unknown>".to_string())).or_default().push(diagnostic);
+		}
+		
+		println!();
+		for (source, mut diagnostics) in grouped {
+			diagnostics.sort_by_key(|v| std::cmp::Reverse(v.severity));
+			println!("{source}");
+			for diagnostic in diagnostics {
+				match diagnostic.severity {
+					Severity::Error => println!("\terror: {diagnostic}"),
+					Severity::Warning => println!("\twarning: {diagnostic}"),
+				}
+			}
+		}
+		
+		let errors = list.iter().filter(|v| v.severity == Severity::Error).count();
+		println!("{errors} error(s), {} warning(s)", list.len() - errors);
+	}
+}
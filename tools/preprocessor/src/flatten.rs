@@ -0,0 +1,211 @@
+use std::{collections::HashMap, path::Path};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use aetherment::modman::{OptionOrStatic, OptionValue, composite};
+use crate::{compression, manifest, metabase, tex, diagnostics::{Diagnostic, Diagnostics}};
+
+// the option values of a preset, options it doesn't set keep their default
+pub struct Values<'a> {
+	colors: HashMap<&'a str, Vec<f32>>,
+	// files option > selected sub option and the ones it inherits from, in the order their files get applied
+	files: Vec<(&'a str, Vec<&'a str>)>,
+}
+
+impl<'a> Values<'a> {
+	pub fn new(meta: &'a metabase::MetaBase, preset: &str) -> Result<Self, crate::Error> {
+		let preset = meta.presets.iter().flatten()
+			.find(|(name, _)| *name == preset)
+			.map(|(_, values)| values)
+			.ok_or_else(|| format!("meta.yaml has no preset '{preset}'"))?;
+		
+		let mut colors = HashMap::new();
+		let mut files = Vec::new();
+		for (name, value) in meta.options.iter().flatten() {
+			match (value, preset.get(name)) {
+				(metabase::OptionBase::Color(value), Some(metabase::ValueBase::Color(v))) => {colors.insert(name.as_str(), value.color(v));}
+				(metabase::OptionBase::Color(value), _) => {colors.insert(name.as_str(), value.color(&value.default));}
+				(metabase::OptionBase::Files(value), selected) => {
+					let subs = value.options.iter().flatten().map(|(k, v)| (k.as_str(), v)).collect::<HashMap<_, _>>();
+					let selected = match selected {
+						Some(metabase::ValueBase::Files(v)) => Some(v.as_str()),
+						_ => value.default.as_deref().or_else(|| value.options.first().and_then(|v| v.keys().next()).map(|v| v.as_str())),
+					};
+					
+					let Some(selected) = selected else {continue};
+					let Some(mut sub) = subs.get_key_value(selected).map(|v| *v.0) else {
+						return Err(format!("Option '{name}' has no sub option '{selected}'").into());
+					};
+					
+					// inherited files first so the selected sub option overwrites them
+					let mut chain = vec![sub];
+					while let Some(inherit) = subs.get(sub).and_then(|v| v.inherit.as_deref()) {
+						if chain.contains(&inherit) {break}
+						chain.insert(0, inherit);
+						sub = inherit;
+					}
+					
+					files.push((name.as_str(), chain));
+				}
+				_ => {}
+			}
+		}
+		
+		Ok(Self{colors, files})
+	}
+	
+	fn selected(&self, option: &str) -> Option<&str> {
+		self.files.iter().find(|(name, _)| *name == option).and_then(|(_, chain)| chain.last().copied())
+	}
+	
+	// value of something depending on options, like the ui colors of meta.yaml
+	fn resolve<T: OptionValue>(&self, value: &OptionOrStatic<T>) -> Option<Vec<f32>> where T::Value: AsRef<[f32]> {
+		let color = |name: &str| self.colors.get(name);
+		match value {
+			OptionOrStatic::Static(v) => Some(v.as_ref().to_vec()),
+			OptionOrStatic::Option(name) => color(name).cloned(),
+			OptionOrStatic::OptionMul(name, v) => Some(color(name)?.iter().zip(v.as_ref()).map(|(a, b)| a * b).collect()),
+			OptionOrStatic::OptionGradiant(a, b, v) => Some(color(a)?.iter().zip(color(b)?).zip(v.as_ref()).map(|((a, b), t)| a + (b - a) * t).collect()),
+			OptionOrStatic::OptionSub(name, values) => Some(values.get(self.selected(name)?)?.as_ref().to_vec()),
+		}
+	}
+	
+	fn resolve_layer(&self, value: &composite::tex::OptionOrStatic<composite::tex::ColorOption>) -> Option<Vec<f32>> {
+		match value {
+			composite::tex::OptionOrStatic::Option(composite::tex::ColorOption(name)) => self.colors.get(name.as_str()).cloned(),
+			composite::tex::OptionOrStatic::Static(v) => Some(v.to_vec()),
+		}
+	}
+	
+	fn resolve_mask(&self, value: &composite::tex::OptionOrStatic<composite::tex::MaskOption>) -> Option<f32> {
+		match value {
+			composite::tex::OptionOrStatic::Option(composite::tex::MaskOption(name)) => crate::composite::factor(self.colors.get(name.as_str())?),
+			composite::tex::OptionOrStatic::Static(v) => Some(*v),
+		}
+	}
+}
+
+// composites every texture of the mod the way aetherment would with the values of a preset, writing a png and tex for every game path
+pub fn flatten(target_root: &Path, meta_path: &Path, preset: &str, out: &Path, game: &dyn crate::game::Source, diag: &Diagnostics) -> Result<(), crate::Error> {
+	let meta = metabase::MetaBase::load(meta_path)?;
+	let values = Values::new(&meta, preset)?;
+	let files_root = target_root.join("files");
+	let paths = textures(&manifest::load(target_root)?, &values).into_iter().collect::<Vec<_>>();
+	let flattened = paths.par_iter().filter(|(game_path, mod_path)| {
+		let result = flatten_file(&files_root, game_path, mod_path, &values, game, diag).and_then(|(width, height, data)| {
+			let path = out.join(game_path);
+			_ = std::fs::create_dir_all(path.parent().unwrap());
+			tex::Tex::new(width as u16, height as u16, &data, compression::Settings::default()).write(&mut std::io::BufWriter::new(std::fs::File::create(&path)?))?;
+			image::RgbaImage::from_raw(width, height, data).ok_or("Pixels don't match the image size")?.save(path.with_extension("png"))?;
+			Ok(())
+		});
+		
+		match result {
+			Ok(_) => true,
+			Err(e) => {
+				diag.push_error(game_path, e);
+				false
+			}
+		}
+	}).count();
+	
+	// ui colors aren't textures, but are just as much a part of what a preset looks like
+	let colors = meta.colors.iter().filter_map(|(id, value)| {
+		let value = value.clone().convert();
+		match values.resolve(&value) {
+			Some(v) => Some((*id, v)),
+			None => {
+				diag.push(Diagnostic::warning(format!("Ui color {id} uses an option that doesn't exist")).source(meta_path.to_string_lossy()));
+				None
+			}
+		}
+	}).collect::<std::collections::BTreeMap<_, _>>();
+	_ = std::fs::create_dir_all(out);
+	std::fs::write(out.join("ui_colors.json"), serde_json::to_string_pretty(&colors)?)?;
+	
+	println!("Flattened {flattened} of {} textures of {preset} into {out:?}", paths.len());
+	
+	Ok(())
+}
+
+// game path > mod path of every texture the preset uses, base files first and the selected options over them in the order of meta.yaml
+pub fn textures(manifest: &manifest::Files, values: &Values) -> HashMap<String, String> {
+	let mut paths = HashMap::new();
+	let selected = values.files.iter().flat_map(|(option, chain)| chain.iter().map(|sub| Some((option.to_string(), sub.to_string()))));
+	for key in std::iter::once(None).chain(selected) {
+		for (game_path, mod_path) in manifest.get(&key).into_iter().flatten() {
+			// composites replace the texture they're for
+			let game_path = game_path.strip_suffix(".comp").unwrap_or(game_path);
+			if game_path.ends_with(".tex") {
+				paths.insert(game_path.to_owned(), mod_path.to_owned());
+			}
+		}
+	}
+	
+	paths
+}
+
+// straight rgba8 of a tex or composite in the files root, layers of game files are read from the game
+// composites using something that can't be flattened fail with a warning rather than an error
+pub fn flatten_file(files_root: &Path, game_path: &str, mod_path: &str, values: &Values, game: &dyn crate::game::Source, diag: &Diagnostics) -> Result<(u32, u32, Vec<u8>), crate::Error> {
+	let read = |mod_path: &str| -> Result<(u32, u32, Vec<u8>), crate::Error> {
+		let tex = tex::Tex::read(&mut std::io::BufReader::new(std::fs::File::open(files_root.join(mod_path))?))?;
+		Ok((tex.header.width as u32, tex.header.height as u32, tex.decode(0)?))
+	};
+	
+	if !mod_path.ends_with(".comp") {
+		return read(mod_path);
+	}
+	
+	let read_layer = |layer: &str, path: &aetherment::modman::Path| -> Result<(u32, u32, Vec<u8>), crate::Error> {
+		match path {
+			aetherment::modman::Path::Mod(path) => read(path),
+			aetherment::modman::Path::Game(path) => match game.tex(path) {
+				Ok(tex) => Ok((tex.width as u32, tex.height as u32, tex.pixels)),
+				Err(e) => Err(Diagnostic::warning(format!("Layer {layer} uses the game file {path}, which couldn't be read, skipped: {e}")).into()),
+			},
+			_ => Err(Diagnostic::warning(format!("Layer {layer} uses {path:?}, only files of the mod and game can be flattened, skipped")).into()),
+		}
+	};
+	
+	let comp = serde_json::from_slice::<composite::tex::Tex>(&std::fs::read(files_root.join(mod_path))?)?;
+	let mut size = None;
+	let mut layers = Vec::new();
+	// the first layer is the top one
+	for layer in comp.layers.iter().rev() {
+		let (width, height, mut data) = read_layer(&layer.name, &layer.path)?;
+		if *size.get_or_insert((width, height)) != (width, height) {
+			return Err(format!("Layer {} is {width}x{height}, which doesn't match the layers below it", layer.name).into());
+		}
+		
+		let mut color = [1.0; 4];
+		let mut unsupported = Vec::new();
+		for modifier in &layer.modifiers {
+			match modifier {
+				composite::tex::Modifier::Color{value} => {
+					let value = values.resolve_layer(value).ok_or_else(|| format!("Layer {} uses a color option that doesn't exist", layer.name))?;
+					color = crate::composite::multiply(color, crate::composite::rgba(&value));
+				}
+				composite::tex::Modifier::AlphaMask{path, cull_point} => {
+					let cull_point = values.resolve_mask(cull_point).ok_or_else(|| format!("Layer {} uses a mask option that doesn't exist", layer.name))?;
+					let (mask_width, mask_height, mask) = read_layer(&layer.name, path)?;
+					if (mask_width, mask_height) != (width, height) {
+						return Err(format!("Mask of layer {} is {mask_width}x{mask_height}, which doesn't match the layer", layer.name).into());
+					}
+					
+					crate::composite::mask(&mut data, &mask, cull_point);
+				}
+				composite::tex::Modifier::AlphaMaskAlphaStretch{..} => unsupported.push("AlphaMaskAlphaStretch"),
+			}
+		}
+		
+		// left out, so the result is close to but not quite what aetherment creates
+		if !unsupported.is_empty() {
+			diag.push(Diagnostic::warning(format!("Layer {} uses modifiers that can't be flattened yet, they were left out: {}", layer.name, unsupported.join(", "))).source(game_path));
+		}
+		
+		layers.push((data, color, layer.blend));
+	}
+	
+	let (width, height) = size.ok_or("Composite has no layers")?;
+	let layers = layers.iter().map(|(rgba, color, blend)| crate::composite::Layer{rgba, color: *color, blend: *blend}).collect::<Vec<_>>();
+	Ok((width, height, crate::composite::flatten(width, height, &layers)))
+}
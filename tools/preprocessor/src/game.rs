@@ -0,0 +1,77 @@
+// where the icons and uld stages read the files of the game from
+use std::path::PathBuf;
+use aetherment::noumenon::format::{external::Bytes, game::{Tex, uld::Uld}};
+
+pub trait Source: Sync {
+	fn tex(&self, path: &str) -> Result<Tex, crate::Error>;
+	fn uld(&self, path: &str) -> Result<Uld, crate::Error>;
+}
+
+// the directory of extracted files if one is given, the installed game otherwise
+pub fn source(args: &crate::cli::Args) -> Box<dyn Source> {
+	match &args.game {
+		Some(root) => Box::new(Directory(root.clone())),
+		None => Box::new(Installed),
+	}
+}
+
+// the game install noumenon finds
+pub struct Installed;
+
+impl Installed {
+	fn file<T: Bytes>(&self, path: &str) -> Result<T, crate::Error> {
+		let noumenon = aetherment::noumenon_instance().ok_or("Invalid Noumenon, use --game to read from a directory of extracted files instead")?;
+		noumenon.file::<T>(path).map_err(|e| format!("Failed reading {path}: {e}").into())
+	}
+}
+
+impl Source for Installed {
+	fn tex(&self, path: &str) -> Result<Tex, crate::Error> {
+		self.file(path)
+	}
+	
+	fn uld(&self, path: &str) -> Result<Uld, crate::Error> {
+		self.file(path)
+	}
+}
+
+// extracted files at their game paths, <root>/ui/icon/062000/062001_hr1.tex
+pub struct Directory(pub PathBuf);
+
+impl Directory {
+	fn file<T: Bytes>(&self, path: &str) -> Result<T, crate::Error> {
+		let data = std::fs::read(self.0.join(path)).map_err(|e| format!("Failed reading {path} from {:?}: {e}", self.0))?;
+		T::read(&mut std::io::Cursor::new(data)).map_err(|e| format!("Failed parsing {path}: {e}").into())
+	}
+}
+
+impl Source for Directory {
+	fn tex(&self, path: &str) -> Result<Tex, crate::Error> {
+		self.file(path)
+	}
+	
+	fn uld(&self, path: &str) -> Result<Uld, crate::Error> {
+		self.file(path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn directory() {
+		let game = Directory(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("game"));
+		
+		// 2x2 of red, green, blue and half transparent white
+		let tex = game.tex("ui/icon/062000/062001_hr1.tex").unwrap();
+		assert_eq!((tex.width, tex.height), (2, 2));
+		assert_eq!(tex.pixels, [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 128]);
+		
+		// without any assets, components or widgets
+		let uld = game.uld("ui/uld/fixture.uld").unwrap();
+		assert!(uld.assets.is_empty() && uld.components.is_empty() && uld.widgets.is_empty());
+		
+		assert!(game.tex("ui/icon/062000/062002_hr1.tex").is_err());
+	}
+}
@@ -0,0 +1,150 @@
+// golden image tests of split_svgs and render_svg, every fixture svg in tests/fixtures gets rendered and compared against tests/golden/<fixture>
+// with tests/fixtures/lib as the library
+// run with UPDATE_GOLDEN=1 to write the current output as the new golden files after an intended change
+
+use std::{collections::BTreeSet, path::{Path, PathBuf}};
+use crate::{artifacts, cache, compression, svg, tex};
+
+// allowed difference per channel, antialiasing can differ slightly between platforms
+const TOLERANCE: u8 = 2;
+
+fn tests_root() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// every file in the directory, relative to it
+fn files(root: &Path) -> BTreeSet<String> {
+	fn walk(dir: &Path, root: &Path, files: &mut BTreeSet<String>) {
+		for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+			let path = entry.path();
+			if path.is_dir() {
+				walk(&path, root, files);
+			} else {
+				files.insert(path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"));
+			}
+		}
+	}
+	
+	let mut files = BTreeSet::new();
+	walk(root, root, &mut files);
+	files
+}
+
+// renders the fixture into a fresh target and returns its files root
+fn render(fixture: &Path, lib: &svg::Library) -> PathBuf {
+	let name = fixture.file_stem().unwrap().to_string_lossy();
+	let target_root = std::env::temp_dir().join(format!("preprocessor-golden-{}-{name}", std::process::id()));
+	_ = std::fs::remove_dir_all(&target_root);
+	let files_root = target_root.join("files");
+	
+	// no fonts so the output doesn't depend on what's installed, fixtures don't use text
+	let font = resvg::usvg::fontdb::Database::new();
+	let artifacts = artifacts::Artifacts{emit: Vec::new(), files_root: files_root.clone(), debug_root: target_root.join("debug")};
+	let data = std::fs::read_to_string(fixture).unwrap();
+	for svg in crate::split_svgs(&data, fixture, lib).unwrap() {
+		// copies of reused elements get ids of their own
+		for (_, layer) in &svg.layers {
+			let root = svg::Element::parse(layer, fixture).unwrap();
+			let mut ids = BTreeSet::new();
+			for id in root.descendants().into_iter().filter_map(|v| v.id()) {
+				assert!(ids.insert(id), "{name}: a layer of {} has the id {id} more than once", svg.path);
+			}
+		}
+		
+		crate::render_svg(svg, 1.0, &files_root, &font, &cache::Cache::default(), &compression::Rules::default(), &artifacts).unwrap();
+	}
+	
+	files_root
+}
+
+// the golden file name of a rendered file, textures are compared as png
+fn golden_name(file: &str) -> String {
+	match file.strip_suffix(".tex") {
+		Some(v) => format!("{v}.png"),
+		None => file.to_owned(),
+	}
+}
+
+fn decode(path: &Path) -> image::RgbaImage {
+	let tex = tex::Tex::read(&mut std::io::BufReader::new(std::fs::File::open(path).unwrap())).unwrap();
+	image::RgbaImage::from_raw(tex.header.width as u32, tex.header.height as u32, tex.decode(0).unwrap()).unwrap()
+}
+
+// what differs between the rendered file and its golden file, if anything
+fn compare(rendered: &Path, golden: &Path) -> Option<String> {
+	if rendered.extension().is_some_and(|v| v == "tex") {
+		let rendered = decode(rendered);
+		let golden = image::open(golden).unwrap().into_rgba8();
+		if rendered.dimensions() != golden.dimensions() {
+			return Some(format!("size is {:?}, golden is {:?}", rendered.dimensions(), golden.dimensions()));
+		}
+		
+		let off = rendered.pixels().zip(golden.pixels())
+			.filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > TOLERANCE))
+			.count();
+		(off > 0).then(|| format!("{off} pixels differ by more than {TOLERANCE}"))
+	} else {
+		let rendered = serde_json::from_slice::<serde_json::Value>(&std::fs::read(rendered).unwrap()).unwrap();
+		let golden = serde_json::from_slice::<serde_json::Value>(&std::fs::read(golden).unwrap()).unwrap();
+		(rendered != golden).then(|| format!("is {rendered}, golden is {golden}"))
+	}
+}
+
+fn update(files_root: &Path, golden_root: &Path) {
+	_ = std::fs::remove_dir_all(golden_root);
+	for file in files(files_root) {
+		let path = golden_root.join(golden_name(&file));
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		if file.ends_with(".tex") {
+			decode(&files_root.join(&file)).save(path).unwrap();
+		} else {
+			std::fs::copy(files_root.join(&file), path).unwrap();
+		}
+	}
+}
+
+#[test]
+fn golden() {
+	let root = tests_root();
+	let updating = std::env::var_os("UPDATE_GOLDEN").is_some();
+	let mut fixtures = std::fs::read_dir(root.join("fixtures")).unwrap()
+		.map(|v| v.unwrap().path())
+		.filter(|v| v.extension().is_some_and(|v| v == "svg"))
+		.collect::<Vec<_>>();
+	fixtures.sort();
+	assert!(!fixtures.is_empty(), "no fixtures found in {:?}", root.join("fixtures"));
+	
+	let lib = svg::Library::load(&root.join("fixtures").join("lib")).unwrap();
+	let mut failures = Vec::new();
+	for fixture in &fixtures {
+		let name = fixture.file_stem().unwrap().to_string_lossy();
+		let golden_root = root.join("golden").join(&*name);
+		let files_root = render(fixture, &lib);
+		
+		if updating {
+			update(&files_root, &golden_root);
+		} else {
+			let rendered = files(&files_root).into_iter().map(|v| golden_name(&v)).collect::<BTreeSet<_>>();
+			let golden = files(&golden_root);
+			for missing in golden.difference(&rendered) {
+				failures.push(format!("{name}: {missing} was not rendered"));
+			}
+			
+			for extra in rendered.difference(&golden) {
+				failures.push(format!("{name}: {extra} has no golden file"));
+			}
+			
+			for file in files(&files_root) {
+				let golden_name = golden_name(&file);
+				if !golden.contains(&golden_name) {continue}
+				if let Some(difference) = compare(&files_root.join(&file), &golden_root.join(&golden_name)) {
+					failures.push(format!("{name}: {file} {difference}"));
+				}
+			}
+		}
+		
+		_ = std::fs::remove_dir_all(files_root.parent().unwrap());
+	}
+	
+	assert!(failures.is_empty(), "output doesn't match the golden files, rerun with UPDATE_GOLDEN=1 if the change is intended:\n{}", failures.join("\n"));
+}
@@ -0,0 +1,309 @@
+use std::path::Path;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use resvg::tiny_skia::Transform;
+use crate::{layer_chain, diagnostics::{Diagnostic, Diagnostics}};
+
+// the guidelines from info.md, lightness and alpha in percent
+// fills of color option layers are tinted by the option, so they should be grey
+const FILLS: [(f32, f32); 5] = [
+	(90.0, 100.0), // backgrounds
+	(100.0, 100.0), // elements
+	(5.0, 100.0), // dark elements
+	(100.0, 60.0), // transparent elements with background color
+	(5.0, 60.0), // transparent elements with (0, 0, 5) color
+];
+const OUTLINE_LAYER: &str = "Background Color";
+const OUTLINE: (f32, f32) = (70.0, 100.0);
+const OUTLINE_WIDTH: f32 = 2.0;
+const OUTLINE_WIDTH_STANDALONE: f32 = 4.0;
+const CORNER_RADIUS: f32 = 10.0;
+const CORNER_RADIUS_STANDALONE: f32 = 22.0;
+// how far off (in percent or px) something can be before it counts as deviating
+const TOLERANCE: f32 = 1.0;
+
+const SHAPES: [&str; 7] = ["rect", "circle", "ellipse", "path", "polygon", "polyline", "line"];
+// elements whose children aren't drawn directly
+const HIDDEN: [&str; 6] = ["defs", "clipPath", "mask", "pattern", "symbol", "marker"];
+
+// the paint relevant to the guidelines, inherited from parent elements
+#[derive(Debug, Clone)]
+struct Paint {
+	fill: Option<String>,
+	fill_opacity: f32,
+	stroke: Option<String>,
+	stroke_opacity: f32,
+	// the value itself if it isn't a length that can be checked
+	stroke_width: Result<f32, String>,
+	// opacity doesn't inherit but does apply to children, so it's multiplied down the tree instead
+	opacity: f32,
+}
+
+impl Default for Paint {
+	fn default() -> Self {
+		Self {
+			fill: Some("#000000".to_string()),
+			fill_opacity: 1.0,
+			stroke: None,
+			stroke_opacity: 1.0,
+			stroke_width: Ok(1.0),
+			opacity: 1.0,
+		}
+	}
+}
+
+impl Paint {
+	fn child(&self, attributes: &[xml::attribute::OwnedAttribute]) -> Self {
+		let mut paint = self.clone();
+		
+		// presentation attributes first, style overrides them
+		let style = attributes.iter().find(|v| v.name.local_name == "style").map_or("", |v| v.value.as_str());
+		let properties = attributes.iter()
+			.map(|v| (v.name.local_name.as_str(), v.value.as_str()))
+			.chain(style.split(";").filter_map(|v| v.split_once(":")).map(|(k, v)| (k.trim(), v.trim())));
+		
+		for (key, value) in properties {
+			match key {
+				"fill" => paint.fill = if value == "none" {None} else {Some(value.to_owned())},
+				"stroke" => paint.stroke = if value == "none" {None} else {Some(value.to_owned())},
+				"fill-opacity" => paint.fill_opacity = value.parse().unwrap_or(1.0),
+				"stroke-opacity" => paint.stroke_opacity = value.parse().unwrap_or(1.0),
+				"stroke-width" => paint.stroke_width = length(value).ok_or_else(|| value.to_owned()),
+				"opacity" => paint.opacity = self.opacity * value.parse().unwrap_or(1.0),
+				_ => {}
+			}
+		}
+		
+		paint
+	}
+}
+
+// hue in degrees, saturation and lightness in percent, None for anything that isn't a plain hex color (gradients and such)
+fn hsl(color: &str) -> Option<(f32, f32, f32)> {
+	let hex = color.strip_prefix("#")?;
+	let hex = match hex.len() {
+		3 => hex.chars().flat_map(|v| [v, v]).collect::<String>(),
+		6 => hex.to_owned(),
+		_ => return None,
+	};
+	
+	let v = u32::from_str_radix(&hex, 16).ok()?;
+	let [r, g, b] = [(v >> 16) & 0xFF, (v >> 8) & 0xFF, v & 0xFF].map(|v| v as f32 / 255.0);
+	let max = r.max(g).max(b);
+	let min = r.min(g).min(b);
+	let l = (max + min) / 2.0;
+	let d = max - min;
+	if d == 0.0 {
+		return Some((0.0, 0.0, l * 100.0));
+	}
+	
+	let s = d / (1.0 - (2.0 * l - 1.0).abs());
+	let h = if max == r {
+		((g - b) / d).rem_euclid(6.0)
+	} else if max == g {
+		(b - r) / d + 2.0
+	} else {
+		(r - g) / d + 4.0
+	} * 60.0;
+	
+	Some((h, s * 100.0, l * 100.0))
+}
+
+// a length in px, None for other units as there's nothing to convert them with
+fn length(value: &str) -> Option<f32> {
+	value.trim().trim_end_matches("px").parse().ok()
+}
+
+// the transform attribute as a matrix, None if it can't be read
+fn transform(value: &str) -> Option<Transform> {
+	let mut transform = Transform::identity();
+	for part in value.split(')') {
+		let part = part.trim().trim_start_matches(',').trim();
+		if part.is_empty() {continue}
+		let (name, args) = part.split_once('(')?;
+		let args = args.split(|c: char| c == ',' || c.is_whitespace())
+			.filter(|v| !v.is_empty())
+			.map(|v| v.parse::<f32>().ok())
+			.collect::<Option<Vec<_>>>()?;
+		
+		transform = transform.pre_concat(match (name.trim(), args.as_slice()) {
+			("matrix", [a, b, c, d, e, f]) => Transform::from_row(*a, *b, *c, *d, *e, *f),
+			("translate", [x]) => Transform::from_translate(*x, 0.0),
+			("translate", [x, y]) => Transform::from_translate(*x, *y),
+			("scale", [v]) => Transform::from_scale(*v, *v),
+			("scale", [x, y]) => Transform::from_scale(*x, *y),
+			("rotate", [angle]) => Transform::from_rotate(*angle),
+			("rotate", [angle, x, y]) => Transform::from_rotate_at(*angle, *x, *y),
+			("skewX", [angle]) => Transform::from_skew(angle.to_radians().tan(), 0.0),
+			("skewY", [angle]) => Transform::from_skew(0.0, angle.to_radians().tan()),
+			_ => return None,
+		});
+	}
+	
+	Some(transform)
+}
+
+// how much the transform scales a stroke, horizontal and vertical lengths
+fn scales(transform: &Transform) -> (f32, f32, f32) {
+	(
+		(transform.sx * transform.sy - transform.kx * transform.ky).abs().sqrt(),
+		(transform.sx * transform.sx + transform.ky * transform.ky).sqrt(),
+		(transform.kx * transform.kx + transform.sy * transform.sy).sqrt(),
+	)
+}
+
+// scaled lengths rounded for the messages, so they don't show float noise
+fn px(value: f32) -> f32 {
+	(value * 100.0).round() / 100.0
+}
+
+fn near(a: f32, b: f32) -> bool {
+	(a - b).abs() <= TOLERANCE
+}
+
+fn format_hsla((h, s, l): (f32, f32, f32), a: f32) -> String {
+	format!("{h:.0}, {s:.0}, {l:.0}, {a:.0} (HSLA)")
+}
+
+// checks the split layers of every svg against the guidelines in info.md, every deviation is a warning
+pub fn check(svg_root: &Path, lib: &crate::svg::Library, diag: &Diagnostics) -> Result<(), crate::Error> {
+	let mut counts = crate::get_svgs(svg_root)?.into_par_iter().filter_map(|file| {
+		let source = file.to_string_lossy().to_string();
+		let result = std::fs::read_to_string(&file)
+			.map_err(|e| e.into())
+			.and_then(|data| crate::split_svgs(&data, &file, lib));
+		
+		match result {
+			Ok(svgs) => {
+				let deviations = svgs.iter().map(|svg| check_svg(&source, svg, diag)).sum::<usize>();
+				Some((source, deviations))
+			}
+			
+			Err(e) => {
+				diag.push_error(source, e);
+				None
+			}
+		}
+	}).filter(|(_, deviations)| *deviations > 0).collect::<Vec<_>>();
+	
+	counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+	for (source, deviations) in &counts {
+		println!("{deviations:>5} {source}");
+	}
+	println!("{} deviations in {} svgs", counts.iter().map(|v| v.1).sum::<usize>(), counts.len());
+	
+	Ok(())
+}
+
+fn check_svg(source: &str, svg: &crate::SvgResult, diag: &Diagnostics) -> usize {
+	let option = svg.option.as_ref().map(|(o1, o2)| format!("{o1}:{o2}"));
+	let standalone = svg.option.as_ref().is_some_and(|(o1, _)| o1 == "Window Style");
+	let radius = match svg.option.as_ref().map(|(o1, o2)| (o1.as_str(), o2.as_str())) {
+		Some(("Window Style", o2)) => Some(if o2.starts_with("Rounded") {CORNER_RADIUS_STANDALONE} else {0.0}),
+		Some(("Shape Style", o2)) => Some(if o2.starts_with("Rounded") {CORNER_RADIUS} else {0.0}),
+		_ => None,
+	};
+	
+	let mut deviations = 0;
+	for (style, layer) in &svg.layers {
+		let color_option = &style.color;
+		let warn = |message: String, id: Option<&String>| {
+			let mut diagnostic = Diagnostic::warning(message)
+				.source(source)
+				.context("layer", layer_chain([svg.path.as_str(), option.as_deref().unwrap_or(""), color_option.as_deref().unwrap_or("")]));
+			if let Some(id) = id {
+				diagnostic = diagnostic.context("id", id);
+			}
+			
+			diag.push(diagnostic);
+		};
+		
+		// paint, if it's inside of an element that isn't drawn directly and the transforms of it and its ancestors
+		let mut stack = vec![(Paint::default(), false, Transform::identity())];
+		for e in xml::EventReader::from_str(layer) {
+			match e {
+				Ok(xml::reader::XmlEvent::StartElement{name, attributes, ..}) => {
+					let (parent, hidden, parent_transform) = stack.last().unwrap();
+					let paint = parent.child(&attributes);
+					let hidden = *hidden || HIDDEN.contains(&name.local_name.as_str());
+					let attribute = |name: &str| attributes.iter().find(|v| v.name.local_name == name).map(|v| &v.value);
+					let id = attribute("id");
+					let transform = match attribute("transform") {
+						Some(value) => transform(value).map(|v| parent_transform.pre_concat(v)).unwrap_or_else(|| {
+							warn(format!("Transform {value} can't be read, sizes inside of it are checked without it"), id);
+							*parent_transform
+						}),
+						None => *parent_transform,
+					};
+					let (stroke_scale, x_scale, y_scale) = scales(&transform);
+					
+					if !hidden && SHAPES.contains(&name.local_name.as_str()) {
+						if let Some(color_option) = color_option {
+							if let Some(hsl) = paint.fill.as_deref().and_then(hsl) {
+								let alpha = paint.fill_opacity * paint.opacity * 100.0;
+								if hsl.1 > TOLERANCE || !FILLS.iter().any(|(l, a)| near(hsl.2, *l) && near(alpha, *a)) {
+									warn(format!("Fill {} is not one of the guideline colors", format_hsla(hsl, alpha)), id);
+									deviations += 1;
+								}
+							}
+							
+							if color_option == OUTLINE_LAYER {
+								if let Some(hsl) = paint.stroke.as_deref().and_then(hsl) {
+									let alpha = paint.stroke_opacity * paint.opacity * 100.0;
+									if hsl.1 > TOLERANCE || !near(hsl.2, OUTLINE.0) || !near(alpha, OUTLINE.1) {
+										warn(format!("Outline {} should be {}", format_hsla(hsl, alpha), format_hsla((0.0, 0.0, OUTLINE.0), OUTLINE.1)), id);
+										deviations += 1;
+									}
+									
+									let width = if standalone {OUTLINE_WIDTH_STANDALONE} else {OUTLINE_WIDTH};
+									match &paint.stroke_width {
+										Ok(stroke_width) if !near(stroke_width * stroke_scale, width) => {
+											warn(format!("Outline is {}px wide, should be {width}px", px(stroke_width * stroke_scale)), id);
+											deviations += 1;
+										}
+										Err(value) => {
+											warn(format!("Outline width {value} can't be checked, only px lengths can"), id);
+											deviations += 1;
+										}
+										_ => {}
+									}
+								}
+							}
+						}
+						
+						if let (Some(radius), "rect") = (radius, name.local_name.as_str()) {
+							let mut parse = |name: &str| {
+								let value = attribute(name)?;
+								let length = length(value);
+								if length.is_none() {
+									warn(format!("Corner radius {name} {value} can't be checked, only px lengths can"), id);
+									deviations += 1;
+								}
+								length
+							};
+							
+							// ry falls back to rx and the other way around
+							let (rx, ry) = (parse("rx"), parse("ry"));
+							if let (Some(rx), Some(ry)) = (rx.or(ry), ry.or(rx)) {
+								let (rx, ry) = (rx * x_scale, ry * y_scale);
+								if (rx != 0.0 || ry != 0.0) && (!near(rx, radius) || !near(ry, radius)) {
+									warn(format!("Corner radius is {}x{}px, should be {radius}px", px(rx), px(ry)), id);
+									deviations += 1;
+								}
+							}
+						}
+					}
+					
+					stack.push((paint, hidden, transform));
+				}
+				
+				Ok(xml::reader::XmlEvent::EndElement{..}) => {
+					stack.pop();
+				}
+				
+				_ => {}
+			}
+		}
+	}
+	
+	deviations
+}
@@ -1,11 +1,12 @@
 use std::{collections::HashMap, path::Path};
 use image::{GenericImage, GenericImageView, Rgba};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use crate::{image_ops, metabase::OptionValueJobsBase};
 
-fn extract(id: usize) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, crate::Error> {
+fn extract(game: &dyn crate::game::Source, id: usize) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, crate::Error> {
 	println!("{}", icon_path(id));
 	
-	let img = aetherment::noumenon_instance().ok_or("Invalid Noumenon")?.file::<aetherment::noumenon::format::game::Tex>(&icon_path(id))?;
+	let img = game.tex(&icon_path(id))?;
 	Ok(image::ImageBuffer::from_vec(img.width as u32, img.height as u32, img.pixels).ok_or("Failed creating image")?)
 }
 
@@ -40,701 +41,576 @@ fn write_comp(dir: &Path, local_dir: &str, layers: Vec<Option<&str>>) -> Result<
 	Ok(())
 }
 
-fn prepare_icon(icon: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, mut alpha_resolver: impl FnMut(&image::Rgba<u8>) -> u8) {
-	let mut min = 255;
-	let mut max = 0;
-	for pixel in icon.pixels_mut().filter(|v| v[3] > 0) {
-		let val = ((pixel.0[0] as f32 * 0.299) as u16 +
-		           (pixel.0[1] as f32 * 0.587) as u16 +
-		           (pixel.0[2] as f32 * 0.144) as u16).min(255) as u8;
-		
-		pixel[0] = val;
-		pixel[1] = val;
-		pixel[2] = val;
-		pixel[3] = alpha_resolver(pixel);
-		
-		min = min.min(val);
-		max = max.max(val);
+// the font icon sheets, job icons go in a grid of 40px cells from y 700 with a half size copy of it at y 180
+const FONT_SIZE: (u32, u32) = (512, 1024);
+const FONT_CELL: u32 = 40;
+const FONT_COLUMNS: u32 = 12;
+const FONT_TOP: u32 = 700;
+const FONT_MASK: &str = "common/font/fonticon_mask.tex";
+
+// icons.yaml, the icon families the icons stage creates from the icons of the game
+#[derive(Debug, serde::Deserialize)]
+pub struct Spec {
+	pub families: Vec<Family>,
+}
+
+impl Spec {
+	pub fn load(path: &Path) -> Result<Self, crate::Error> {
+		// steps and layers are written as single key maps (- blur: 0.5) instead of yaml tags
+		let data = std::fs::read(path).map_err(|e| format!("Failed reading {path:?}: {e}"))?;
+		Ok(serde_yaml::with::singleton_map_recursive::deserialize(serde_yaml::Deserializer::from_slice(&data))?)
 	}
+}
+
+// a game icon id, written with its leading zeros like in the paths of the game (061901), which yaml reads as text
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(try_from = "IdValue")]
+pub struct Id(pub usize);
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum IdValue {
+	Number(usize),
+	Text(String),
+}
+
+impl TryFrom<IdValue> for Id {
+	type Error = String;
 	
-	let scale = (max - min) as f32;
-	for pixel in icon.pixels_mut().filter(|v| v[3] > 0) {
-		let val = ((pixel.0[0] - min) as f32 / scale * 32.0) as u8 + (255 - 32);
-		pixel[0] = val;
-		pixel[1] = val;
-		pixel[2] = val;
+	fn try_from(value: IdValue) -> Result<Self, Self::Error> {
+		match value {
+			IdValue::Number(v) => Ok(Id(v)),
+			IdValue::Text(v) => v.parse().map(Id).map_err(|_| format!("'{v}' is not an icon id")),
+		}
 	}
 }
 
-fn add_border(icon: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>) {
-	let (w, h) = (icon.width() as isize, icon.height() as isize);
-	let mut new = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::new(w as u32, h as u32);
-	for x in 0..w {
-		for y in 0..h {
-			let mut max = 0;
-			for x2 in (x - 2).max(0)..=(x + 2).min(w - 1) {
-				for y2 in (y - 2).max(0)..=(y + 2).min(h - 1) {
-					let dist = ((x2 as f32 - x as f32).powi(2) + (y2 as f32 - y as f32).powi(2)).sqrt();
-					max = max.max((icon.get_pixel(x2 as u32, y2 as u32)[3] as f32 * (1.0 - (dist - 2.0).clamp(0.0, 1.0))) as u8)
-				}
-			}
-			
-			let pixel = new.get_pixel_mut(x as u32, y as u32);
-			pixel[0] = 12;
-			pixel[1] = 12;
-			pixel[2] = 12;
-			pixel[3] = max;
+#[derive(Debug, serde::Deserialize)]
+pub struct Family {
+	pub name: String,
+	// first and last id of the game icons it's made from
+	pub ids: Option<(Id, Id)>,
+	#[serde(default)]
+	pub exclude: Vec<Id>,
+	#[serde(default)]
+	pub until_missing: bool,
+	// or the svgs it's drawn from instead
+	pub assets: Option<AssetIcons>,
+	pub images: Vec<Image>,
+	pub outputs: Vec<Output>,
+}
+
+impl Family {
+	// the lowest id, which the place of an icon in the family is relative to
+	fn first(&self) -> usize {
+		match (&self.ids, &self.assets) {
+			(Some(ids), _) => ids.0.0,
+			(None, Some(assets)) => assets.icons.iter().map(|v| v.id.0).min().unwrap_or(0),
+			(None, None) => 0,
 		}
 	}
-	
-	image::imageops::overlay(&mut new, icon, 0, 0);
-	*icon = new;
-}
-
-fn center(icon: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, vertical_only: bool) {
-	let (mut min_x, mut min_y, mut max_x, mut max_y) = (9999, 9999, 0, 0);
-	let (w, h) = (icon.width(), icon.height());
-	for x in 0..w {
-		for y in 0..h {
-			if icon.get_pixel(x, y)[3] > 50 {
-				min_x = min_x.min(x);
-				min_y = min_y.min(y);
-				max_x = max_x.max(x);
-				max_y = max_y.max(y);
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AssetIcons {
+	// svgs are scaled to fit into a square of size, padding away from its edges
+	pub size: u32,
+	#[serde(default)]
+	pub padding: u32,
+	pub icons: Vec<AssetIcon>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AssetIcon {
+	pub id: Id,
+	// in vector_assets
+	pub asset: String,
+	// the role whose color option the role color of its layers is
+	pub role: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Image {
+	pub name: String,
+	// an earlier image to start from instead of the game icon
+	pub from: Option<String>,
+	#[serde(default)]
+	pub steps: Vec<Step>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+	// grayscale, the range of it spread over levels
+	Normalize{alpha: Alpha, levels: (u8, u8)},
+	Center{#[serde(default)] vertical_only: bool, threshold: u8},
+	Border{radius: f32, color: [u8; 3]},
+	Glow{radius: f32, strength: f32, color: [u8; 3]},
+	ClearBorder{width: u32, corner: u32},
+	Crop{x: u32, y: u32, width: u32, height: u32, pad: u32},
+	Threshold{red: u8, green: u8},
+	Blur(f32),
+	Thicken,
+	Desaturate,
+	Overlay(String),
+}
+
+// where the alpha of a normalized icon comes from
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alpha {
+	// alpha from start to 1 becomes 0 to 1
+	Ramp(f32),
+	// for icons without transparency, pixels at or below min_alpha are dropped
+	Luminance{min_alpha: u8, scale: f32, offset: f32, #[serde(default)] invert: bool},
+}
+
+impl Alpha {
+	// the pixel is already grayscale
+	fn resolve(&self, pixel: &Rgba<u8>) -> u8 {
+		match *self {
+			Alpha::Ramp(start) => (((pixel[3] as f32 / 255.0).max(start) - start) / (1.0 - start) * 255.0) as u8,
+			Alpha::Luminance{min_alpha, scale, offset, invert} => {
+				if pixel[3] <= min_alpha {return 0}
+				let value = (pixel[0] as f32 * scale + offset).clamp(0.0, 255.0);
+				(if invert {255.0 - value} else {value}) as u8
 			}
 		}
 	}
-	
-	let offset_x = if vertical_only {0} else {(w as i32 - (max_x + min_x) as i32) / 2};
-	let offset_y = (h as i32 - (max_y + min_y) as i32) / 2;
-	let mut new = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::new(w, h);
-	for x in offset_x.abs().max(0)..(w as i32 - offset_x.abs()) {
-		for y in offset_y.abs()..(h as i32 - offset_y.abs()) {
-			new.put_pixel(x as u32, y as u32, icon.get_pixel((x - offset_x) as u32, (y - offset_y) as u32).to_owned())
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Output {
+	// game icon id relative to the one it's made from
+	#[serde(default)]
+	pub offset: usize,
+	// font icon sheets (common/font/fonticon_<sheet>.tex) to add it to instead
+	#[serde(default)]
+	pub font: Vec<String>,
+	pub option: Option<(String, String)>,
+	pub size: Option<u32>,
+	pub icon_size: Option<u32>,
+	#[serde(default)]
+	pub skip_colors: Vec<String>,
+	pub layers: Vec<OutputLayer>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct OutputLayer {
+	#[serde(flatten)]
+	pub source: Source,
+	pub color: Option<String>,
+	pub fade: Option<f32>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+	Image(String),
+	Asset(String),
+	Plate(u32),
+}
+
+fn resize(image: &image::RgbaImage, size: u32) -> image::RgbaImage {
+	if image.dimensions() == (size, size) {return image.clone()}
+	image::imageops::resize(image, size, size, image::imageops::FilterType::CatmullRom)
+}
+
+fn load_asset(path: &Path) -> Result<image::RgbaImage, crate::Error> {
+	if path.extension().is_some_and(|v| v == "svg") {
+		let tree = resvg::usvg::Tree::from_data(&std::fs::read(path)?, &resvg::usvg::Options::default(), &resvg::usvg::fontdb::Database::new())?;
+		let size = tree.size().to_int_size();
+		let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("Failed creating pixmap with specified size")?;
+		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+		Ok(image::RgbaImage::from_vec(size.width(), size.height(), pixmap.take()).ok_or_else(|| format!("Failed loading in {path:?}"))?)
+	} else {
+		Ok(image::open(path)?.into_rgba8())
+	}
+}
+
+fn fit_asset(path: &Path, size: u32, padding: u32) -> Result<image::RgbaImage, crate::Error> {
+	let tree = resvg::usvg::Tree::from_data(&std::fs::read(path)?, &resvg::usvg::Options::default(), &resvg::usvg::fontdb::Database::new())?;
+	let (w, h) = (tree.size().width(), tree.size().height());
+	let scale = size.saturating_sub(padding * 2) as f32 / w.max(h);
+	let transform = resvg::tiny_skia::Transform::from_translate((size as f32 - w * scale) / 2.0, (size as f32 - h * scale) / 2.0).pre_scale(scale, scale);
+	let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size).ok_or("Failed creating pixmap with specified size")?;
+	resvg::render(&tree, transform, &mut pixmap.as_mut());
+	Ok(image::RgbaImage::from_fn(size, size, |x, y| {
+		let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+		[pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()].into()
+	}))
+}
+
+// a white square with its corners cut off diagonally
+fn plate(size: u32, corner: u32) -> image::RgbaImage {
+	let mut plate = image::RgbaImage::from_pixel(size, size, [255, 255, 255, 255].into());
+	let z = || -> image::Rgba<u8> {[0, 0, 0, 0].into()};
+	for x in 0..corner {
+		for y in 0..(corner - x) {
+			plate.put_pixel(x, y, z());
+			plate.put_pixel(size - 1 - x, y, z());
+			plate.put_pixel(x, size - 1 - y, z());
+			plate.put_pixel(size - 1 - x, size - 1 - y, z());
 		}
 	}
 	
-	*icon = new;
-}
-
-pub fn job_icons(target_root: &Path) -> Result<HashMap<(&str, &str), HashMap<String, String>>, crate::Error> {
-	let icon_roles = HashMap::from([
-		(1, "Pld Color"), // gla
-		(2, "Mnk Color"), // pgl
-		(3, "War Color"), // mrd
-		(4, "Drg Color"), // lnc
-		(5, "Brd Color"), // arc
-		(6, "Whm Color"), // cnj
-		(7, "Blm Color"), // thm
-		(8, "Crp Color"), // crp
-		(9, "Bsm Color"), // bsm
-		(10, "Arm Color"), // arm
-		(11, "Gsm Color"), // gsm
-		(12, "Ltw Color"), // ltw
-		(13, "Wvr Color"), // wvr
-		(14, "Alc Color"), // alc
-		(15, "Cul Color"), // cul
-		(16, "Min Color"), // min
-		(17, "Bot Color"), // bot
-		(18, "Fsh Color"), // fsh
-		(19, "Pld Color"), // pld
-		(20, "Mnk Color"), // mnk
-		(21, "War Color"), // war
-		(22, "Drg Color"), // drg
-		(23, "Brd Color"), // brd
-		(24, "Whm Color"), // whm
-		(25, "Blm Color"), // blm
-		(26, "Smn Color"), // acn
-		(27, "Smn Color"), // smn
-		(28, "Sch Color"), // sch
-		(29, "Nin Color"), // rog
-		(30, "Nin Color"), // nin
-		(31, "Mch Color"), // mch
-		(32, "Drk Color"), // drk
-		(33, "Ast Color"), // ast
-		(34, "Sam Color"), // sam
-		(35, "Rdm Color"), // rdm
-		(36, "Blu Color"), // blu
-		(37, "Gnb Color"), // gnb
-		(38, "Dnc Color"), // dnc
-		(39, "Rpr Color"), // rpr
-		(40, "Sge Color"), // sge
-		(41, "Vpr Color"), // vpr
-		(42, "Pct Color"), // pct
-		(43, "No Job Color"), // chocobo
-		(44, "No Job Color"), // carbuncle
-		(45, "No Job Color"), // free slot
-	]);
-	
-	// load in extra assets
-	let asset_dir = target_root.join("vector_assets").join("job icon backgrounds");
-	let opt = resvg::usvg::Options::default();
-	let font = resvg::usvg::fontdb::Database::new();
-	
-	let rounded_64 = {
-		let tree = resvg::usvg::Tree::from_data(&std::fs::read(asset_dir.join("rounded_64.svg"))?, &opt, &font)?;
-		let mut pixmap = resvg::tiny_skia::Pixmap::new(64, 64).ok_or("Failed creating pixmap with specified size")?;
-		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
-		image::RgbaImage::from_vec(64, 64, pixmap.data().to_owned()).ok_or("Failed loading in rounded_64.svg")?
-	};
-	
-	let square_64 = {
-		let tree = resvg::usvg::Tree::from_data(&std::fs::read(asset_dir.join("square_64.svg"))?, &opt, &font)?;
-		let mut pixmap = resvg::tiny_skia::Pixmap::new(64, 64).ok_or("Failed creating pixmap with specified size")?;
-		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
-		image::RgbaImage::from_vec(64, 64, pixmap.data().to_owned()).ok_or("Failed loading in square_64.svg")?
-	};
-	
-	let nameplate_rounded = {
-		let tree = resvg::usvg::Tree::from_data(&std::fs::read(asset_dir.join("nameplate_rounded.svg"))?, &opt, &font)?;
-		let mut pixmap = resvg::tiny_skia::Pixmap::new(64, 64).ok_or("Failed creating pixmap with specified size")?;
-		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
-		image::RgbaImage::from_vec(64, 64, pixmap.data().to_owned()).ok_or("Failed loading in nameplate_rounded.svg")?
-	};
-	
-	let nameplate_square = {
-		let tree = resvg::usvg::Tree::from_data(&std::fs::read(asset_dir.join("nameplate_square.svg"))?, &opt, &font)?;
-		let mut pixmap = resvg::tiny_skia::Pixmap::new(64, 64).ok_or("Failed creating pixmap with specified size")?;
-		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
-		image::RgbaImage::from_vec(64, 64, pixmap.data().to_owned()).ok_or("Failed loading in nameplate_square.svg")?
-	};
-	
-	let action_80 = image::open(asset_dir.join("action_80.png"))?.into_rgba8();
-	
-	// font icons
-	let mut font_icons_glow = HashMap::new();
-	let mut font_icons_border = HashMap::new();
-	let mut font_icons_square = HashMap::new();
-	let mut font_icons_rounded = HashMap::new();
-	
-	// do the thing
-	let mut files = HashMap::new();
-	let files_root = target_root.join("files");
-	for i in 1..=99 {
-		let content_id = 062000 + i;
-		let party_id   = 062100 + i;
-		let macro_id   = 062800 + i;
-		let plate_id   = 062225 + i;
-		let Ok(mut icon_56) = extract(content_id) else {break};
-		// let color = icon_roles[&i].option();
-		let color = icon_roles[&i];
-		
-		// greyscale, shove it into the 191-255 range, and do some alpha stuff
-		prepare_icon(&mut icon_56, |pixel| (((pixel[3] as f32 / 255.0).max(0.75) - 0.75) * 4.0 * 255.0) as u8);
-		center(&mut icon_56, true);
-		
-		// (nearly)black border
-		let mut icon_border_56 = icon_56.clone();
-		add_border(&mut icon_border_56);
-		
-		// icon_border_80 = image::imageops::blur(&icon_border_80, 2.0);
-		image::imageops::overlay(&mut icon_border_56, &icon_56, 0, 0);
-		
-		// glow
-		let mut icon_glow_56 = image::imageops::blur(&icon_56, 4.0);
-		for pixel in icon_glow_56.pixels_mut() {
-			pixel[0] = 255;
-			pixel[1] = 255;
-			pixel[2] = 255;
-			pixel[3] = (pixel[3] as u16 * 4).min(255) as u8;
-		}
-		
-		//// save em all
-		// content icons
-		{
-			let content_path = icon_path(content_id);
-			{ // content glow
-				let local_dir = format!("{}/Job Icons Content/Glow", content_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(56, 56, icon_glow_56.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(56, 56, icon_56.as_raw(), &dir.join("1.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), None])?;
-				files.entry(("Job Icons Content", "Glow")).or_insert_with(|| HashMap::new()).insert(format!("{content_path}.comp"), format!("{local_dir}/comp.tex.comp"));
+	plate
+}
+
+fn run_step(step: &Step, icon: &mut image::RgbaImage, images: &HashMap<&str, image::RgbaImage>) -> Result<(), crate::Error> {
+	match step {
+		Step::Normalize{alpha, levels} => {
+			image_ops::grayscale(icon);
+			let range = image_ops::range(icon);
+			for pixel in icon.pixels_mut().filter(|v| v[3] > 0) {
+				pixel[3] = alpha.resolve(pixel);
 			}
 			
-			{ // content border
-				let local_dir = format!("{}/Job Icons Content/Border", content_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(56, 56, icon_border_56.as_raw(), &dir.join("0.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color)])?;
-				files.entry(("Job Icons Content", "Border")).or_insert_with(|| HashMap::new()).insert(format!("{content_path}.comp"), format!("{local_dir}/comp.tex.comp"));
+			if let Some(range) = range {
+				image_ops::levels(icon, range, *levels);
 			}
 		}
 		
-		// party list icons
-		{
-			let icon_64 = image::imageops::resize(&icon_56, 64, 64, image::imageops::FilterType::CatmullRom);
-			let icon_border_64 = image::imageops::resize(&icon_border_56, 64, 64, image::imageops::FilterType::CatmullRom);
-			let icon_glow_64 = image::imageops::resize(&icon_glow_56, 64, 64, image::imageops::FilterType::CatmullRom);
-			
-			let mut icon_faded_64 = icon_64.clone();
-			for pixel in icon_faded_64.pixels_mut() {pixel[3] = (pixel[3] as f32 * 0.8) as u8;}
-			
-			let party_path = icon_path(party_id);
-			{ // party glow
-				let local_dir = format!("{}/Job Icons Party List/Glow", party_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, icon_glow_64.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(64, 64, icon_64.as_raw(), &dir.join("1.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), None])?;
-				files.entry(("Job Icons Party List", "Glow")).or_insert_with(|| HashMap::new()).insert(format!("{party_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // party border
-				let local_dir = format!("{}/Job Icons Party List/Border", party_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, icon_border_64.as_raw(), &dir.join("0.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color)])?;
-				files.entry(("Job Icons Party List", "Border")).or_insert_with(|| HashMap::new()).insert(format!("{party_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // party square
-				let local_dir = format!("{}/Job Icons Party List/Square", party_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, square_64.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(64, 64, icon_border_64.as_raw(), &dir.join("1.tex"))?;
-				crate::save_tex(64, 64, icon_faded_64.as_raw(), &dir.join("2.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), Some(color), None])?;
-				files.entry(("Job Icons Party List", "Square")).or_insert_with(|| HashMap::new()).insert(format!("{party_path}.comp"), format!("{local_dir}/comp.tex.comp"));
+		Step::Center{vertical_only, threshold} => *icon = image_ops::center(icon, *threshold, *vertical_only),
+		Step::Border{radius, color} => *icon = image_ops::outline(icon, *radius, *color),
+		Step::Glow{radius, strength, color} => *icon = image_ops::glow(icon, *radius, *strength, *color),
+		Step::ClearBorder{width, corner} => {
+			let z = || -> image::Rgba<u8> {[0, 0, 0, 0].into()};
+			let s = icon.width();
+			for x in 0..s {
+				for y in 0..*width {
+					icon.put_pixel(x, y, z());
+					icon.put_pixel(x, s - 1 - y, z());
+					icon.put_pixel(y, x, z());
+					icon.put_pixel(s - 1 - y, x, z());
+				}
 			}
 			
-			{ // party rounded
-				let local_dir = format!("{}/Job Icons Party List/Rounded", party_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, rounded_64.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(64, 64, icon_border_64.as_raw(), &dir.join("1.tex"))?;
-				crate::save_tex(64, 64, icon_faded_64.as_raw(), &dir.join("2.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), Some(color), None])?;
-				files.entry(("Job Icons Party List", "Rounded")).or_insert_with(|| HashMap::new()).insert(format!("{party_path}.comp"), format!("{local_dir}/comp.tex.comp"));
+			for x in 0..*corner {
+				for y in 0..(corner - x) {
+					icon.put_pixel(width + x, width + y, z());
+					icon.put_pixel(s - 1 - width - x, width + y, z());
+					icon.put_pixel(width + x, s - 1 - width - y, z());
+					icon.put_pixel(s - 1 - width - x, s - 1 - width - y, z());
+				}
 			}
 		}
 		
-		// macro icons
-		{
-			let icon_80 = image::imageops::resize(&icon_56, 80, 80, image::imageops::FilterType::CatmullRom);
-			let icon_border_80 = image::imageops::resize(&icon_border_56, 80, 80, image::imageops::FilterType::CatmullRom);
-			let icon_glow_80 = image::imageops::resize(&icon_glow_56, 80, 80, image::imageops::FilterType::CatmullRom);
-			
-			let mut icon_faded_80 = icon_80.clone();
-			for pixel in icon_faded_80.pixels_mut() {pixel[3] = (pixel[3] as f32 * 0.8) as u8;}
-			
-			let macro_path = icon_path(macro_id);
-			{ // macro glow
-				let local_dir = format!("{}/Job Icons Macro/Glow", macro_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(80, 80, icon_glow_80.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(80, 80, icon_80.as_raw(), &dir.join("1.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), None])?;
-				files.entry(("Job Icons Macro", "Glow")).or_insert_with(|| HashMap::new()).insert(format!("{macro_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // macro border
-				let local_dir = format!("{}/Job Icons Macro/Border", macro_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(80, 80, icon_border_80.as_raw(), &dir.join("0.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color)])?;
-				files.entry(("Job Icons Macro", "Border")).or_insert_with(|| HashMap::new()).insert(format!("{macro_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // macro full
-				let local_dir = format!("{}/Job Icons Macro/Full", macro_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(80, 80, action_80.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(80, 80, icon_border_80.as_raw(), &dir.join("1.tex"))?;
-				crate::save_tex(80, 80, icon_faded_80.as_raw(), &dir.join("2.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), Some(color), None])?;
-				files.entry(("Job Icons Macro", "Full")).or_insert_with(|| HashMap::new()).insert(format!("{macro_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
+		Step::Crop{x, y, width, height, pad} => {
+			let mut new = image::RgbaImage::new(width + pad * 2, height + pad * 2);
+			image::imageops::overlay(&mut new, &icon.sub_image(*x, *y, *width, *height).to_image(), *pad as i64, *pad as i64);
+			*icon = new;
 		}
 		
-		// nameplate type 1
-		{
-			let mut icon_64: image::ImageBuffer<Rgba<u8>, _> = image::ImageBuffer::new(64, 64);
-			let mut icon_border_64: image::ImageBuffer<Rgba<u8>, _> = image::ImageBuffer::new(64, 64);
-			let mut icon_glow_64: image::ImageBuffer<Rgba<u8>, _> = image::ImageBuffer::new(64, 64);
-			
-			image::imageops::overlay(&mut icon_64, &image::imageops::resize(&icon_56, 48, 48, image::imageops::FilterType::CatmullRom), 8, 8);
-			image::imageops::overlay(&mut icon_border_64, &image::imageops::resize(&icon_border_56, 48, 48, image::imageops::FilterType::CatmullRom), 8, 8);
-			image::imageops::overlay(&mut icon_glow_64, &image::imageops::resize(&icon_glow_56, 48, 48, image::imageops::FilterType::CatmullRom), 8, 8);
-			
-			let mut icon_faded_64 = icon_64.clone();
-			for pixel in icon_faded_64.pixels_mut() {pixel[3] = (pixel[3] as f32 * 0.8) as u8;}
-			
-			let plate_path = icon_path(plate_id);
-			{ // party glow
-				let local_dir = format!("{}/Job Icons Party List/Glow", plate_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, icon_glow_64.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(64, 64, icon_64.as_raw(), &dir.join("1.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), None])?;
-				files.entry(("Job Icons Party List", "Glow")).or_insert_with(|| HashMap::new()).insert(format!("{plate_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // party border
-				let local_dir = format!("{}/Job Icons Party List/Border", plate_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, icon_border_64.as_raw(), &dir.join("0.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color)])?;
-				files.entry(("Job Icons Party List", "Border")).or_insert_with(|| HashMap::new()).insert(format!("{plate_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // party square
-				let local_dir = format!("{}/Job Icons Party List/Square", plate_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, nameplate_square.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(64, 64, icon_border_64.as_raw(), &dir.join("1.tex"))?;
-				crate::save_tex(64, 64, icon_faded_64.as_raw(), &dir.join("2.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), Some(color), None])?;
-				files.entry(("Job Icons Party List", "Square")).or_insert_with(|| HashMap::new()).insert(format!("{plate_path}.comp"), format!("{local_dir}/comp.tex.comp"));
-			}
-			
-			{ // party rounded
-				let local_dir = format!("{}/Job Icons Party List/Rounded", plate_path);
-				let dir = files_root.join(&local_dir);
-				_ = std::fs::create_dir_all(&dir);
-				
-				crate::save_tex(64, 64, nameplate_rounded.as_raw(), &dir.join("0.tex"))?;
-				crate::save_tex(64, 64, icon_border_64.as_raw(), &dir.join("1.tex"))?;
-				crate::save_tex(64, 64, icon_faded_64.as_raw(), &dir.join("2.tex"))?;
-				write_comp(&dir, &local_dir, vec![Some(color), Some(color), None])?;
-				files.entry(("Job Icons Party List", "Rounded")).or_insert_with(|| HashMap::new()).insert(format!("{plate_path}.comp"), format!("{local_dir}/comp.tex.comp"));
+		Step::Threshold{red, green} => {
+			for pixel in icon.pixels_mut().filter(|v| v[3] > 0) {
+				*pixel = if pixel[0] > *red && pixel[1] > *green {[255, 255, 255, 255]} else {[pixel[0], pixel[1], pixel[2], 0]}.into();
 			}
 		}
 		
-		// font icons
-		// if !matches!(icon_roles[&i], Roles::Other) {
-		if color != "No Job Color" {
-			let icon_36 = image::imageops::resize(&icon_56, 36, 36, image::imageops::FilterType::CatmullRom);
-			let icon_border_36 = image::imageops::resize(&icon_border_56, 36, 36, image::imageops::FilterType::CatmullRom);
-			let icon_glow_36 = image::imageops::resize(&icon_glow_56, 36, 36, image::imageops::FilterType::CatmullRom);
-			let square_36 = image::imageops::resize(&square_64, 36, 36, image::imageops::FilterType::CatmullRom);
-			let rounded_36 = image::imageops::resize(&rounded_64, 36, 36, image::imageops::FilterType::CatmullRom);
-			
-			let mut icon_faded_36 = icon_36.clone();
-			for pixel in icon_faded_36.pixels_mut() {pixel[3] = (pixel[3] as f32 * 0.8) as u8;}
-			
-			let x = (((i - 1) % 12) * 40 + 2) as i64;
-			let y = (700 + (i - 1) / 12 * 40 + 2) as i64;
-			
-			{ // party glow
-				let entry = font_icons_glow.entry(color).or_insert_with(|| vec![
-					(true, image::ImageBuffer::new(512, 1024)),
-					(false, image::ImageBuffer::new(512, 1024)),
-				]);
-				
-				image::imageops::overlay(&mut entry[0].1, &icon_glow_36, x, y);
-				image::imageops::overlay(&mut entry[1].1, &icon_36, x, y);
-			}
-			
-			{ // party border
-				let entry = font_icons_border.entry(color).or_insert_with(|| vec![
-					(true, image::ImageBuffer::new(512, 1024)),
-				]);
-				
-				image::imageops::overlay(&mut entry[0].1, &icon_border_36, x, y);
-			}
-			
-			{ // party square
-				let entry = font_icons_square.entry(color).or_insert_with(|| vec![
-					(true, image::ImageBuffer::new(512, 1024)),
-					(true, image::ImageBuffer::new(512, 1024)),
-					(false, image::ImageBuffer::new(512, 1024)),
-				]);
-				
-				image::imageops::overlay(&mut entry[0].1, &square_36, x, y);
-				image::imageops::overlay(&mut entry[1].1, &icon_border_36, x, y);
-				image::imageops::overlay(&mut entry[2].1, &icon_faded_36, x, y);
-			}
-			
-			{ // party rounded
-				let entry = font_icons_rounded.entry(color).or_insert_with(|| vec![
-					(true, image::ImageBuffer::new(512, 1024)),
-					(true, image::ImageBuffer::new(512, 1024)),
-					(false, image::ImageBuffer::new(512, 1024)),
-				]);
-				
-				image::imageops::overlay(&mut entry[0].1, &rounded_36, x, y);
-				image::imageops::overlay(&mut entry[1].1, &icon_border_36, x, y);
-				image::imageops::overlay(&mut entry[2].1, &icon_faded_36, x, y);
-			}
+		Step::Blur(sigma) => *icon = image_ops::blur(icon, *sigma),
+		Step::Thicken => {
+			let c = icon.clone();
+			image::imageops::overlay(icon, &c, 0, 0);
 		}
+		
+		Step::Desaturate => image_ops::grayscale(icon),
+		Step::Overlay(name) => image::imageops::overlay(icon, images.get(name.as_str()).ok_or_else(|| format!("Image '{name}' doesn't exist yet"))?, 0, 0),
 	}
 	
-	// font icons
-	fn make_smalls(full: &mut HashMap<&str, Vec<(bool, image::ImageBuffer<Rgba<u8>, Vec<u8>>)>>) {
-		for (_, layers) in full {
-			for (_, img) in layers.iter_mut() {
-				image::imageops::overlay(img, &image::imageops::resize(&img.view(0, 700, 512, 324).to_image(), 256, 162, image::imageops::FilterType::CatmullRom), 0, 180);
+	Ok(())
+}
+
+// the layers of an output for one icon, bottom first, None if it's skipped
+fn output_layers(output: &Output, images: &HashMap<&str, image::RgbaImage>, shared: &Shared, colors: &IconColors) -> Result<Option<Vec<ColoredImage>>, crate::Error> {
+	let mut layers = Vec::new();
+	for layer in &output.layers {
+		let color = match layer.color.as_deref() {
+			Some("job") => Some(colors.job.ok_or("Icon has no job color")?.to_owned()),
+			Some("role") => Some(colors.role.ok_or("Icon has no role color")?.to_owned()),
+			color => color.map(|v| v.to_owned()),
+		};
+		
+		if color.as_ref().is_some_and(|v| output.skip_colors.contains(v)) {return Ok(None)}
+		
+		let mut image = match &layer.source {
+			Source::Image(name) => {
+				let image = images.get(name.as_str()).ok_or_else(|| format!("Image '{name}' doesn't exist"))?;
+				match (output.size, output.icon_size) {
+					(Some(size), Some(icon_size)) => {
+						let mut canvas = image::RgbaImage::new(size, size);
+						let offset = (size as i64 - icon_size as i64) / 2;
+						image::imageops::overlay(&mut canvas, &resize(image, icon_size), offset, offset);
+						canvas
+					}
+					
+					(Some(size), None) => resize(image, size),
+					_ => image.clone(),
+				}
 			}
+			
+			Source::Asset(name) => {
+				let asset = &shared.assets[name.as_str()];
+				output.size.map_or_else(|| asset.clone(), |size| resize(asset, size))
+			}
+			
+			Source::Plate(corner) => plate(output.size.ok_or("Plates need the size of the output")?, *corner),
+		};
+		
+		if let Some(fade) = layer.fade {
+			image_ops::fade(&mut image, fade);
 		}
+		
+		layers.push((color, image));
 	}
 	
-	fn write_icon_font(dir: &Path, local_dir: &str, game_path: &str, full: &HashMap<&str, Vec<(bool, image::ImageBuffer<Rgba<u8>, Vec<u8>>)>>) -> Result<(), crate::Error> {
-		use aetherment::modman::{Path, composite::tex::*};
-		
-		let comp = Tex {
-			layers: {
-				// let mut layers = layers.into_iter().enumerate().map(|(i, color_option)| {
-				let mut layers = full.iter()
-					.flat_map(|(color, layers)| layers.iter().map(|v| (color.to_owned(), v)))
-					.enumerate()
-					.map(|(i, (color_option, (use_color, img)))| {
-						crate::save_tex(512, 1024, img.as_raw(), &dir.join(format!("{i}.tex"))).unwrap();
-						
-						Layer {
-							name: format!("Layer{i}"),
-							path: Path::Mod(format!("{local_dir}/{i}.tex")),
-							blend: Blend::Normal,
-							modifiers: if *use_color {
-								vec![
-									Modifier::Color {
-										value: OptionOrStatic::Option(ColorOption(color_option.to_string()))
-									}
-								]
-							} else {
-								Vec::new()
-							}
-						}
-					}).collect::<Vec<_>>();
-				
-				layers.push(Layer {
-					name: "Game".to_string(),
-					path: Path::Game(game_path.to_string()),
-					blend: Blend::Normal,
-					modifiers: vec![
-						Modifier::AlphaMask {
-							path: Path::Mod(format!("common/font/fonticon_mask.tex")),
-							cull_point: OptionOrStatic::Static(0.5),
-						},
-					]
-				});
-				
-				layers.reverse();
-				layers
-			}
+	Ok(Some(layers))
+}
+
+// a layer of an output and its color option
+type ColoredImage = (Option<String>, image::RgbaImage);
+// option, game path and mod path
+type FileEntry = (Option<(String, String)>, String, String);
+// the layers of font icon sheets by the color options of the layers
+type Sheets = HashMap<Vec<Option<String>>, Vec<image::RgbaImage>>;
+
+// what the outputs of every icon of a family use besides its images
+struct Shared<'a> {
+	assets: HashMap<&'a str, image::RgbaImage>,
+	jobs: Option<&'a OptionValueJobsBase>,
+}
+
+// the color options the job and role colors of the layers of an icon are
+struct IconColors<'a> {
+	job: Option<&'a str>,
+	role: Option<&'a str>,
+}
+
+// what one icon of a family created, the files and its layers of every font output
+struct Created {
+	files: Vec<FileEntry>,
+	font: Vec<(usize, Vec<ColoredImage>)>,
+}
+
+fn create(family: &Family, id: usize, source: image::RgbaImage, shared: &Shared, files_root: &Path, tex: &crate::compression::Rules, artifacts: &crate::artifacts::Artifacts) -> Result<Created, crate::Error> {
+	let mut images = HashMap::new();
+	for image in &family.images {
+		let mut icon = match &image.from {
+			Some(from) => images.get(from.as_str()).cloned().ok_or_else(|| format!("Image '{from}' doesn't exist yet"))?,
+			None => source.clone(),
 		};
 		
-		std::fs::write(dir.join("comp.tex.comp"), serde_json::to_string(&comp)?)?;
+		for step in &image.steps {
+			run_step(step, &mut icon, &images)?;
+		}
 		
-		Ok(())
+		images.insert(image.name.as_str(), icon);
 	}
 	
-	make_smalls(&mut font_icons_glow);
-	make_smalls(&mut font_icons_border);
-	make_smalls(&mut font_icons_square);
-	make_smalls(&mut font_icons_rounded);
+	let index = id - family.first() + 1;
+	let role = family.assets.iter().flat_map(|v| &v.icons).find(|v| v.id.0 == id).and_then(|v| v.role.as_deref());
+	let colors = IconColors {
+		job: shared.jobs.and_then(|v| v.icon_color(index)),
+		role: shared.jobs.zip(role).and_then(|(jobs, role)| jobs.role_color(role)),
+	};
 	
-	for sheet in ["ps3", "ps4", "ps5", "lys", "xinput"] {
-		let path = format!("common/font/fonticon_{sheet}.tex");
-		{ // party glow
-			let local_dir = format!("{path}/Job Icons Party List/Glow");
-			let dir = files_root.join(&local_dir);
-			_ = std::fs::create_dir_all(&dir);
-			
-			write_icon_font(&dir, &local_dir, &path, &font_icons_glow)?;
-			files.entry(("Job Icons Party List", "Glow")).or_insert_with(|| HashMap::new()).insert(format!("{path}.comp"), format!("{local_dir}/comp.tex.comp"));
+	let mut created = Created{files: Vec::new(), font: Vec::new()};
+	for (i, output) in family.outputs.iter().enumerate() {
+		let Some(layers) = output_layers(output, &images, shared, &colors)? else {continue};
+		if !output.font.is_empty() {
+			created.font.push((i, layers));
+			continue;
 		}
 		
-		{ // party border
-			let local_dir = format!("{path}/Job Icons Party List/Border");
-			let dir = files_root.join(&local_dir);
-			_ = std::fs::create_dir_all(&dir);
-			
-			write_icon_font(&dir, &local_dir, &path, &font_icons_border)?;
-			files.entry(("Job Icons Party List", "Border")).or_insert_with(|| HashMap::new()).insert(format!("{path}.comp"), format!("{local_dir}/comp.tex.comp"));
+		let path = icon_path(id + output.offset);
+		let (width, height) = layers.first().ok_or("Output has no layers")?.1.dimensions();
+		if layers.iter().any(|(_, v)| v.dimensions() != (width, height)) {
+			return Err(format!("Layers of {path} don't have the same size").into());
 		}
 		
-		{ // party square
-			let local_dir = format!("{path}/Job Icons Party List/Square");
-			let dir = files_root.join(&local_dir);
-			_ = std::fs::create_dir_all(&dir);
+		let local_dir = match &output.option {
+			Some((o1, o2)) => format!("{path}/{o1}/{o2}"),
+			None => path.clone(),
+		};
+		
+		if let [(None, image)] = layers.as_slice() {
+			let local_path = if output.option.is_some() {format!("{local_dir}/0.tex")} else {path.clone()};
+			let file = files_root.join(&local_path);
+			_ = std::fs::create_dir_all(file.parent().unwrap());
 			
-			write_icon_font(&dir, &local_dir, &path, &font_icons_square)?;
-			files.entry(("Job Icons Party List", "Square")).or_insert_with(|| HashMap::new()).insert(format!("{path}.comp"), format!("{local_dir}/comp.tex.comp"));
+			crate::save_tex(width as u16, height as u16, image.as_raw(), &file, tex.file(&path), artifacts)?;
+			created.files.push((output.option.clone(), path, local_path));
+			continue;
 		}
 		
-		{ // party rounded
-			let local_dir = format!("{path}/Job Icons Party List/Rounded");
-			let dir = files_root.join(&local_dir);
-			_ = std::fs::create_dir_all(&dir);
-			
-			write_icon_font(&dir, &local_dir, &path, &font_icons_rounded)?;
-			files.entry(("Job Icons Party List", "Rounded")).or_insert_with(|| HashMap::new()).insert(format!("{path}.comp"), format!("{local_dir}/comp.tex.comp"));
+		let dir = files_root.join(&local_dir);
+		_ = std::fs::create_dir_all(&dir);
+		
+		for (i, (_, image)) in layers.iter().enumerate() {
+			crate::save_tex(width as u16, height as u16, image.as_raw(), &dir.join(format!("{i}.tex")), tex.layer(&path), artifacts)?;
 		}
+		write_comp(&dir, &local_dir, layers.iter().map(|(color, _)| color.as_deref()).collect())?;
+		created.files.push((output.option.clone(), format!("{path}.comp"), format!("{local_dir}/comp.tex.comp")));
 	}
 	
-	// let mask = image::ImageBuffer::<Rgba<u8>, Vec<u8>>::from_fn(512, 1024, |_, y| if y < 180 || (y >= 342 && y < 700) {[255, 255, 255, 255]} else {[0, 0, 0, 255]}.into());
-	let mask = image::ImageBuffer::<Rgba<u8>, Vec<u8>>::from_fn(512, 1024, |_, y| if (y >= 180 && y < 180 + 20 * 4) || (y >= 700 && y < 700 + 40 * 4) {[0, 0, 0, 255]} else {[255, 255, 255, 255]}.into());
-	crate::save_tex(512, 1024, mask.as_raw(), &files_root.join("common/font/fonticon_mask.tex")).unwrap();
-	
-	Ok(files)
+	Ok(created)
 }
 
-pub fn tribe_icons(target_root: &Path) -> Result<HashMap<String, String>, crate::Error> {
-	let files_root = target_root.join("files");
-	// let files = (061901..=061959).into_par_iter().filter_map(|id| {(|| -> Result<Option<(String, String)>, crate::Error> {
-	let files = (061901..=061919).into_par_iter().filter_map(|id| {(|| -> Result<Option<(String, String)>, crate::Error> {
-		let Ok(mut icon) = extract(id) else {return Ok(None)};
-		
-		prepare_icon(&mut icon, |pixel| if pixel[3] > 200 {((pixel[0] as f32 * 4.0) - 512.0).clamp(0.0, 255.0) as u8} else {0});
-		add_border(&mut icon);
-		
-		let local_path = icon_path(id);
-		let dir = files_root.join(&local_path);
-		_ = std::fs::create_dir_all(&dir);
+// every family of the spec, job colors come from the jobs option of the meta
+pub fn icons(spec_path: &Path, meta_path: &Path, game: &dyn crate::game::Source, target_root: &Path, tex: &crate::compression::Rules, artifacts: &crate::artifacts::Artifacts, diag: &crate::diagnostics::Diagnostics) -> Result<crate::manifest::Files, crate::Error> {
+	let spec = Spec::load(spec_path)?;
+	let jobs = crate::metabase::MetaBase::load(meta_path)?.jobs;
+	let mut files = crate::manifest::Files::new();
+	let mut fonts = false;
+	for family in &spec.families {
+		let family_files = run_family(family, jobs.as_ref(), game, target_root, tex, artifacts, diag).map_err(|e| format!("Family {}: {e}", family.name))?;
+		for (option, game_path, mod_path) in family_files {
+			files.entry(option).or_default().insert(game_path, mod_path);
+		}
 		
-		crate::save_tex(64, 64, icon.as_raw(), &dir.join("0.tex"))?;
-		write_comp(&dir, &local_path, vec![Some("Foreground Color")])?;
-		Ok(Some((format!("{local_path}.comp"), format!("{local_path}/comp.tex.comp"))))
-	})().unwrap()}).collect();
+		fonts |= family.outputs.iter().any(|v| !v.font.is_empty());
+	}
+	
+	if fonts {
+		// cull the job icons of the game, where ours go
+		let mask = image::RgbaImage::from_fn(FONT_SIZE.0, FONT_SIZE.1, |_, y| if (180..180 + 20 * 4).contains(&y) || (FONT_TOP..FONT_TOP + FONT_CELL * 4).contains(&y) {[0, 0, 0, 255]} else {[255, 255, 255, 255]}.into());
+		crate::save_tex(FONT_SIZE.0 as u16, FONT_SIZE.1 as u16, mask.as_raw(), &target_root.join("files").join(FONT_MASK), tex.layer(FONT_MASK), artifacts)?;
+	}
 	
 	Ok(files)
 }
 
-pub fn silver_bordered(target_root: &Path) -> Result<HashMap<String, String>, crate::Error> {
+fn run_family(family: &Family, jobs: Option<&OptionValueJobsBase>, game: &dyn crate::game::Source, target_root: &Path, tex: &crate::compression::Rules, artifacts: &crate::artifacts::Artifacts, diag: &crate::diagnostics::Diagnostics) -> Result<Vec<FileEntry>, crate::Error> {
 	let files_root = target_root.join("files");
-	let files = (061751..=061874).into_par_iter().filter_map(|id| {(|| -> Result<Option<(String, String)>, crate::Error> {
-		if id == 061800 {return Ok(None)};
-		let Ok(mut icon) = extract(id) else {return Ok(None)};
-		
-		let z = || -> image::Rgba<u8> {[0, 0, 0, 0].into()};
-		let s = icon.width();
-		for x in 0..s {
-			for y in 0..6 {
-				icon.put_pixel(x, y, z());
-				icon.put_pixel(x, s - 1 - y, z());
-				icon.put_pixel(y, x, z());
-				icon.put_pixel(s - 1 - y, x, z());
+	let assets_root = target_root.join("vector_assets");
+	let mut assets = HashMap::new();
+	for layer in family.outputs.iter().flat_map(|v| &v.layers) {
+		if let Source::Asset(name) = &layer.source {
+			if !assets.contains_key(name.as_str()) {
+				assets.insert(name.as_str(), load_asset(&assets_root.join(name))?);
 			}
 		}
-		
-		for x in 0..4 {
-			for y in 0..(4 - x) {
-				icon.put_pixel(6 + x, 6 + y, z());
-				icon.put_pixel(s - 7 - x, 6 + y, z());
-				icon.put_pixel(6 + x, s - 7 - y, z());
-				icon.put_pixel(s - 7 - x, s - 7 - y, z());
+	}
+	
+	let shared = Shared{assets, jobs};
+	
+	let sources = match (&family.ids, &family.assets) {
+		(Some((first, last)), None) => {
+			let ids = (first.0..=last.0).filter(|id| !family.exclude.contains(&Id(*id)));
+			if family.until_missing {
+				ids.map_while(|id| Some((id, extract(game, id).ok()?))).collect::<Vec<_>>()
+			} else {
+				ids.collect::<Vec<_>>().into_par_iter().filter_map(|id| Some((id, extract(game, id).ok()?))).collect()
 			}
 		}
 		
-		// icon = image::imageops::resize(&icon.sub_image(6, 6, 52, 52).to_image(), 64, 64, image::imageops::FilterType::CatmullRom);
+		(None, Some(assets)) => assets.icons.iter()
+			.map(|v| Ok((v.id.0, fit_asset(&assets_root.join(&v.asset), assets.size, assets.padding)?)))
+			.collect::<Result<Vec<_>, crate::Error>>()?,
 		
-		let local_path = icon_path(id);
-		let path = files_root.join(&local_path);
-		_ = std::fs::create_dir_all(&path.parent().unwrap());
-		
-		crate::save_tex(64, 64, icon.as_raw(), &path)?;
-		Ok(Some((local_path.clone(), local_path)))
-	})().unwrap()}).collect();
+		_ => return Err("Families are made from either game icons (ids) or assets".into()),
+	};
 	
-	Ok(files)
-}
-
-pub fn shop_icons(target_root: &Path) -> Result<HashMap<String, String>, crate::Error> {
-	let files_root = target_root.join("files");
-	let files = (060101..=060199).into_par_iter().filter_map(|id| {(|| -> Result<Option<(String, String)>, crate::Error> {
-		if id == 060158 {return Ok(None)}; // some quest marker icon, why??
-		let Ok(mut icon) = extract(id) else {return Ok(None)};
-		
-		let mut new = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::new(40, 40);
-		image::imageops::overlay(&mut new, &icon.sub_image(4, 4, 32, 32).to_image(), 4, 4);
-		icon = new;
-		
-		prepare_icon(&mut icon, |pixel| if pixel[3] > 240 {(255.0 - ((pixel[0] as f32 * 30.0) - 2048.0).clamp(0.0, 255.0)) as u8} else {0});
-		add_border(&mut icon);
-		
-		let local_path = icon_path(id);
-		let path = files_root.join(&local_path);
-		_ = std::fs::create_dir_all(&path.parent().unwrap());
+	let mut created = sources.into_par_iter().filter_map(|(id, source)| {
+		create(family, id, source, &shared, &files_root, tex, artifacts)
+			.map(|v| (id, v))
+			.map_err(|e| diag.push_error(icon_path(id), e))
+			.ok()
+	}).collect::<Vec<_>>();
+	created.sort_by_key(|(id, _)| *id);
+	
+	let mut files = created.iter_mut().flat_map(|(_, v)| std::mem::take(&mut v.files)).collect::<Vec<_>>();
+	for (i, output) in family.outputs.iter().enumerate().filter(|(_, v)| !v.font.is_empty()) {
+		// a set of sheets for every combination of colors, they become the layers of the composite
+		let mut sheets = Sheets::new();
+		for (id, created) in &created {
+			let index = (id - family.first()) as u32;
+			let x = (index % FONT_COLUMNS * FONT_CELL + 2) as i64;
+			let y = (FONT_TOP + index / FONT_COLUMNS * FONT_CELL + 2) as i64;
+			for (_, layers) in created.font.iter().filter(|(output, _)| *output == i) {
+				let entry = sheets.entry(layers.iter().map(|(color, _)| color.clone()).collect())
+					.or_insert_with(|| layers.iter().map(|_| image::RgbaImage::new(FONT_SIZE.0, FONT_SIZE.1)).collect());
+				for (sheet, (_, image)) in entry.iter_mut().zip(layers) {
+					image::imageops::overlay(sheet, image, x, y);
+				}
+			}
+		}
 		
-		crate::save_tex(40, 40, icon.as_raw(), &path)?;
-		Ok(Some((local_path.clone(), local_path)))
-	})().unwrap()}).collect();
+		make_smalls(&mut sheets);
+		for sheet in &output.font {
+			let path = format!("common/font/fonticon_{sheet}.tex");
+			let local_dir = match &output.option {
+				Some((o1, o2)) => format!("{path}/{o1}/{o2}"),
+				None => path.clone(),
+			};
+			
+			let dir = files_root.join(&local_dir);
+			_ = std::fs::create_dir_all(&dir);
+			
+			write_icon_font(&dir, &local_dir, &path, &sheets, tex, artifacts)?;
+			files.push((output.option.clone(), format!("{path}.comp"), format!("{local_dir}/comp.tex.comp")));
+		}
+	}
 	
 	Ok(files)
 }
 
-pub fn menu_icons(target_root: &Path) -> Result<HashMap<String, String>, crate::Error> {
-	let mut bg: image::ImageBuffer<Rgba<u8>, _> = image::ImageBuffer::from_pixel(80, 80, [255, 255, 255, 255].into());
-	let z = || -> image::Rgba<u8> {[0, 0, 0, 0].into()};
-	let s = bg.width();
-	for x in 0..6 {
-		for y in 0..(6 - x) {
-			bg.put_pixel(x, y, z());
-			bg.put_pixel(s - 1 - x, y, z());
-			bg.put_pixel(x, s - 1 - y, z());
-			bg.put_pixel(s - 1 - x, s - 1 - y, z());
+// the half size copy of the job icons
+fn make_smalls(sheets: &mut Sheets) {
+	for layers in sheets.values_mut() {
+		for img in layers.iter_mut() {
+			image::imageops::overlay(img, &image::imageops::resize(&img.view(0, FONT_TOP, FONT_SIZE.0, FONT_SIZE.1 - FONT_TOP).to_image(), FONT_SIZE.0 / 2, (FONT_SIZE.1 - FONT_TOP) / 2, image::imageops::FilterType::CatmullRom), 0, 180);
 		}
 	}
+}
+
+fn write_icon_font(dir: &Path, local_dir: &str, game_path: &str, sheets: &Sheets, tex: &crate::compression::Rules, artifacts: &crate::artifacts::Artifacts) -> Result<(), crate::Error> {
+	use aetherment::modman::{Path, composite::tex::*};
 	
-	let files_root = target_root.join("files");
-	let files = (000001..=000099).into_par_iter().filter_map(|id| {(|| -> Result<Option<(String, String)>, crate::Error> {
-		let Ok(mut icon) = extract(id) else {return Ok(None)};
-		
-		for pixel in icon.pixels_mut().filter(|v| v[3] > 0) {
-			if pixel[0] > 100 && pixel[1] > 70 /*|| pixel[2] == 24*/ {
-				// pixel[3] = ((pixel[0] as f32 - 100.0) * 8.0).clamp(0.0, 255.0) as u8;
-				pixel[0] = 255;
-				pixel[1] = 255;
-				pixel[2] = 255;
-				pixel[3] = 255;
-			} else {
-				pixel[3] = 0;
-			}
-		}
-		
-		let mut icon = image::imageops::blur(&icon, 0.5);
-		let c = icon.clone();
-		image::imageops::overlay(&mut icon, &c, 0, 0);
-		// image::imageops::overlay(&mut icon, &c, 0, 0);
-		add_border(&mut icon);
-		
-		// needed since for some reason it shades red/brown? (premultied bs most likely idfk)
-		for pixel in icon.pixels_mut().filter(|v| v[3] > 0) {
-			let val = ((pixel.0[0] as f32 * 0.299) as u16 +
-			           (pixel.0[1] as f32 * 0.587) as u16 +
-			           (pixel.0[2] as f32 * 0.144) as u16).min(255) as u8;
+	let comp = Tex {
+		layers: {
+			let mut layers = sheets.iter()
+				.flat_map(|(colors, layers)| colors.iter().zip(layers))
+				.enumerate()
+				.map(|(i, (color_option, img))| -> Result<Layer, crate::Error> {
+					crate::save_tex(FONT_SIZE.0 as u16, FONT_SIZE.1 as u16, img.as_raw(), &dir.join(format!("{i}.tex")), tex.layer(game_path), artifacts)?;
+					
+					Ok(Layer {
+						name: format!("Layer{i}"),
+						path: Path::Mod(format!("{local_dir}/{i}.tex")),
+						blend: Blend::Normal,
+						modifiers: if let Some(color_option) = color_option {
+							vec![
+								Modifier::Color {
+									value: OptionOrStatic::Option(ColorOption(color_option.to_string()))
+								}
+							]
+						} else {
+							Vec::new()
+						}
+					})
+				}).collect::<Result<Vec<_>, _>>()?;
 			
-			pixel[0] = val;
-			pixel[1] = val;
-			pixel[2] = val;
+			layers.push(Layer {
+				name: "Game".to_string(),
+				path: Path::Game(game_path.to_string()),
+				blend: Blend::Normal,
+				modifiers: vec![
+					Modifier::AlphaMask {
+						path: Path::Mod(FONT_MASK.to_string()),
+						cull_point: OptionOrStatic::Static(0.5),
+					},
+				]
+			});
+			
+			layers.reverse();
+			layers
 		}
-		
-		let path = icon_path(id);
-		let dir = files_root.join(&path);
-		_ = std::fs::create_dir_all(&dir);
-		
-		crate::save_tex(80, 80, bg.as_raw(), &dir.join("0.tex"))?;
-		crate::save_tex(80, 80, icon.as_raw(), &dir.join("1.tex"))?;
-		write_comp(&dir, &path, vec![Some("Secondary Color"), Some("Foreground Color")])?;
-		Ok(Some((format!("{path}.comp"), format!("{path}/comp.tex.comp"))))
-	})().unwrap()}).collect();
+	};
 	
-	Ok(files)
+	std::fs::write(dir.join("comp.tex.comp"), serde_json::to_string(&comp)?)?;
+	
+	Ok(())
 }
 
 /*
@@ -750,4 +626,35 @@ ranges:
 061751-061874 = silver bordered
 061901-061959 = beast tribe
 060101-060199 = shop icons
-*/
\ No newline at end of file
+062581-062586 = role icons
+*/
+#[cfg(test)]
+mod tests {
+	// the icons stage needs the game, so mistakes in icons.yaml would otherwise only show up there
+	#[test]
+	fn spec() {
+		let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+		let spec = super::Spec::load(&root.join("icons.yaml")).unwrap();
+		for family in &spec.families {
+			assert!(family.ids.is_some() != family.assets.is_some(), "{}: needs either ids or assets", family.name);
+			for icon in family.assets.iter().flat_map(|v| &v.icons) {
+				assert!(root.join("vector_assets").join(&icon.asset).exists(), "{}: asset '{}' doesn't exist", family.name, icon.asset);
+			}
+			
+			let images = family.images.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+			for (i, image) in family.images.iter().enumerate() {
+				if let Some(from) = &image.from {
+					assert!(images[..i].contains(&from.as_str()), "{}: image {} starts from '{from}', which comes after it or doesn't exist", family.name, image.name);
+				}
+			}
+			
+			for layer in family.outputs.iter().flat_map(|v| &v.layers) {
+				match &layer.source {
+					super::Source::Image(name) => assert!(images.contains(&name.as_str()), "{}: image '{name}' doesn't exist", family.name),
+					super::Source::Asset(name) => assert!(root.join("vector_assets").join(name).exists(), "{}: asset '{name}' doesn't exist", family.name),
+					super::Source::Plate(_) => {}
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,210 @@
+// the image operations icons are made with, on straight rgba8
+// colors of transparent pixels are meaningless, so anything mixing pixels does so on premultiplied colors
+use image::{Rgba, RgbaImage};
+
+// rec. 601 luma
+pub fn luma(pixel: &Rgba<u8>) -> u8 {
+	(pixel[0] as f32 * 0.299 + pixel[1] as f32 * 0.587 + pixel[2] as f32 * 0.114).round().min(255.0) as u8
+}
+
+pub fn grayscale(image: &mut RgbaImage) {
+	for pixel in image.pixels_mut() {
+		let value = luma(pixel);
+		pixel[0] = value;
+		pixel[1] = value;
+		pixel[2] = value;
+	}
+}
+
+// darkest and brightest value of the visible pixels of a grayscale image
+pub fn range(image: &RgbaImage) -> Option<(u8, u8)> {
+	image.pixels().filter(|v| v[3] > 0).fold(None, |range, v| match range {
+		Some((min, max)) => Some((v[0].min(min), v[0].max(max))),
+		None => Some((v[0], v[0])),
+	})
+}
+
+// maps the colors of visible pixels from one range to another, anything outside of it gets clamped
+pub fn levels(image: &mut RgbaImage, from: (u8, u8), to: (u8, u8)) {
+	let scale = if from.1 > from.0 {(to.1 as f32 - to.0 as f32) / (from.1 - from.0) as f32} else {0.0};
+	for pixel in image.pixels_mut().filter(|v| v[3] > 0) {
+		for c in 0..3 {
+			let value = to.0 as f32 + (pixel[c].clamp(from.0, from.1.max(from.0)) - from.0) as f32 * scale;
+			pixel[c] = value.round().clamp(0.0, 255.0) as u8;
+		}
+	}
+}
+
+// a solid outline behind the image, reaching radius pixels out from it with an antialiased edge
+pub fn outline(image: &RgbaImage, radius: f32, color: [u8; 3]) -> RgbaImage {
+	let (w, h) = (image.width() as i64, image.height() as i64);
+	let reach = radius.ceil() as i64;
+	let mut outline = RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+		let (x, y) = (x as i64, y as i64);
+		let mut max = 0;
+		for x2 in (x - reach).max(0)..=(x + reach).min(w - 1) {
+			for y2 in (y - reach).max(0)..=(y + reach).min(h - 1) {
+				let dist = (((x2 - x).pow(2) + (y2 - y).pow(2)) as f32).sqrt();
+				max = max.max((image.get_pixel(x2 as u32, y2 as u32)[3] as f32 * (1.0 - (dist - radius).clamp(0.0, 1.0))) as u8);
+			}
+		}
+		
+		Rgba([color[0], color[1], color[2], max])
+	});
+	
+	image::imageops::overlay(&mut outline, image, 0, 0);
+	outline
+}
+
+// gaussian blur
+pub fn blur(image: &RgbaImage, sigma: f32) -> RgbaImage {
+	let premultiplied = image::Rgba32FImage::from_fn(image.width(), image.height(), |x, y| {
+		let [r, g, b, a] = image.get_pixel(x, y).0.map(|v| v as f32 / 255.0);
+		Rgba([r * a, g * a, b * a, a])
+	});
+	
+	let blurred = image::imageops::blur(&premultiplied, sigma);
+	RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+		let [r, g, b, a] = blurred.get_pixel(x, y).0;
+		let straight = |v: f32| if a > 0.0 {(v / a * 255.0).round().clamp(0.0, 255.0) as u8} else {0};
+		Rgba([straight(r), straight(g), straight(b), (a * 255.0).round().clamp(0.0, 255.0) as u8])
+	})
+}
+
+// a blurred silhouette of the image in a single color, its alpha multiplied by strength
+pub fn glow(image: &RgbaImage, sigma: f32, strength: f32, color: [u8; 3]) -> RgbaImage {
+	let mut glow = blur(image, sigma);
+	for pixel in glow.pixels_mut() {
+		*pixel = Rgba([color[0], color[1], color[2], (pixel[3] as f32 * strength).min(255.0) as u8]);
+	}
+	
+	glow
+}
+
+pub fn fade(image: &mut RgbaImage, opacity: f32) {
+	for pixel in image.pixels_mut() {
+		pixel[3] = (pixel[3] as f32 * opacity) as u8;
+	}
+}
+
+// moves the bounding box of the pixels more opaque than the threshold to the middle, only up or down if vertical_only
+pub fn center(image: &RgbaImage, threshold: u8, vertical_only: bool) -> RgbaImage {
+	let visible = image.enumerate_pixels().filter(|(_, _, v)| v[3] > threshold);
+	let Some((min_x, min_y, max_x, max_y)) = visible.fold(None, |bounds, (x, y, _)| match bounds {
+		Some((min_x, min_y, max_x, max_y)) => Some((x.min(min_x), y.min(min_y), x.max(max_x), y.max(max_y))),
+		None => Some((x, y, x, y)),
+	}) else {
+		return image.clone();
+	};
+	
+	let (w, h) = (image.width() as i64, image.height() as i64);
+	let offset_x = if vertical_only {0} else {(w - 1 - (min_x + max_x) as i64) / 2};
+	let offset_y = (h - 1 - (min_y + max_y) as i64) / 2;
+	RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+		let (x, y) = (x as i64 - offset_x, y as i64 - offset_y);
+		if x < 0 || y < 0 || x >= w || y >= h {return Rgba([0, 0, 0, 0])}
+		*image.get_pixel(x as u32, y as u32)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn image(w: u32, h: u32, pixels: &[(u32, u32, [u8; 4])]) -> RgbaImage {
+		let mut image = RgbaImage::new(w, h);
+		for (x, y, v) in pixels {
+			image.put_pixel(*x, *y, Rgba(*v));
+		}
+		
+		image
+	}
+	
+	#[test]
+	fn luma_weights() {
+		assert_eq!(luma(&Rgba([255, 255, 255, 255])), 255);
+		assert_eq!(luma(&Rgba([255, 0, 0, 255])), 76);
+		assert_eq!(luma(&Rgba([0, 255, 0, 255])), 150);
+		assert_eq!(luma(&Rgba([0, 0, 255, 255])), 29);
+		
+		let mut gray = image(1, 1, &[(0, 0, [0, 0, 255, 100])]);
+		grayscale(&mut gray);
+		assert_eq!(gray.get_pixel(0, 0).0, [29, 29, 29, 100]);
+	}
+	
+	#[test]
+	fn levels_remap() {
+		let mut gray = image(4, 1, &[(0, 0, [10, 10, 10, 255]), (1, 0, [20, 20, 20, 255]), (2, 0, [30, 30, 30, 255]), (3, 0, [0, 0, 0, 0])]);
+		let from = range(&gray).unwrap();
+		assert_eq!(from, (10, 30));
+		levels(&mut gray, from, (223, 255));
+		assert_eq!(gray.pixels().map(|v| v[0]).collect::<Vec<_>>(), [223, 239, 255, 0]);
+		
+		// a flat image has nowhere to spread to
+		let mut flat = image(1, 1, &[(0, 0, [50, 50, 50, 255])]);
+		levels(&mut flat, (50, 50), (223, 255));
+		assert_eq!(flat.get_pixel(0, 0)[0], 223);
+	}
+	
+	#[test]
+	fn outline_radius() {
+		let dot = image(9, 9, &[(4, 4, [255, 255, 255, 255])]);
+		let outlined = outline(&dot, 2.0, [12, 12, 12]);
+		assert_eq!(outlined.get_pixel(4, 4).0, [255, 255, 255, 255]);
+		assert_eq!(outlined.get_pixel(6, 4).0, [12, 12, 12, 255]);
+		assert_eq!(outlined.get_pixel(7, 4)[3], 0);
+		// sqrt(5) is a bit further than the radius
+		assert_eq!(outlined.get_pixel(6, 5)[3], (255.0 * (1.0 - (5f32.sqrt() - 2.0))) as u8);
+		
+		let wider = outline(&dot, 3.0, [200, 0, 0]);
+		assert_eq!(wider.get_pixel(7, 4).0, [200, 0, 0, 255]);
+	}
+	
+	#[test]
+	fn blur_premultiplied() {
+		// the transparent red pixels around it must not tint it
+		let mut dot = RgbaImage::from_pixel(5, 5, Rgba([255, 0, 0, 0]));
+		dot.put_pixel(2, 2, Rgba([255, 255, 255, 255]));
+		let blurred = blur(&dot, 1.0);
+		for pixel in blurred.pixels().filter(|v| v[3] > 0) {
+			assert_eq!(&pixel.0[..3], [255, 255, 255]);
+		}
+		assert!(blurred.get_pixel(2, 2)[3] < 255);
+		assert!(blurred.get_pixel(1, 2)[3] > 0);
+	}
+	
+	#[test]
+	fn glow_strength() {
+		let dot = image(9, 9, &[(4, 4, [0, 0, 0, 255])]);
+		let weak = glow(&dot, 2.0, 1.0, [255, 255, 255]);
+		let strong = glow(&dot, 2.0, 4.0, [0, 255, 0]);
+		assert_eq!(&strong.get_pixel(3, 4).0[..3], [0, 255, 0]);
+		assert_eq!(strong.get_pixel(3, 4)[3], (weak.get_pixel(3, 4)[3] as f32 * 4.0).min(255.0) as u8);
+		assert!(strong.get_pixel(2, 4)[3] > weak.get_pixel(2, 4)[3]);
+	}
+	
+	#[test]
+	fn fade_alpha() {
+		let mut pixels = image(2, 1, &[(0, 0, [1, 2, 3, 200]), (1, 0, [1, 2, 3, 255])]);
+		fade(&mut pixels, 0.5);
+		assert_eq!(pixels.get_pixel(0, 0).0, [1, 2, 3, 100]);
+		assert_eq!(pixels.get_pixel(1, 0).0, [1, 2, 3, 127]);
+	}
+	
+	#[test]
+	fn center_bounds() {
+		// a 2x1 box at the top left of a 6x5 image, the faint pixel doesn't count
+		let corner = image(6, 5, &[(0, 0, [9, 9, 9, 255]), (1, 0, [9, 9, 9, 255]), (5, 4, [9, 9, 9, 10])]);
+		let centered = center(&corner, 50, false);
+		assert_eq!(centered.get_pixel(2, 2).0, [9, 9, 9, 255]);
+		assert_eq!(centered.get_pixel(3, 2).0, [9, 9, 9, 255]);
+		assert_eq!(centered.get_pixel(0, 0)[3], 0);
+		
+		let vertical = center(&corner, 50, true);
+		assert_eq!(vertical.get_pixel(0, 2).0, [9, 9, 9, 255]);
+		assert_eq!(vertical.get_pixel(1, 2).0, [9, 9, 9, 255]);
+		
+		let empty = RgbaImage::new(3, 3);
+		assert_eq!(center(&empty, 50, false), empty);
+	}
+}
@@ -0,0 +1,341 @@
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use crate::{metabase, svg, Ancestor, Location, diagnostics::{Diagnostic, Diagnostics, Severity}};
+
+// game path and the option it belongs to (None being the base files)
+type GamePath = (String, Option<(String, String)>);
+
+// the option names svg labels are allowed to use
+struct Known {
+	// option > sub options
+	options: HashMap<String, HashSet<String>>,
+	colors: HashSet<String>,
+}
+
+// checks every svg against the layer label conventions split_svgs expects and the options in meta.yaml
+pub fn lint(svg_root: &Path, lib: &svg::Library, meta_path: &Path, diag: &Diagnostics) -> Result<(), crate::Error> {
+	let meta = metabase::MetaBase::load(meta_path)?;
+	let mut known = Known {
+		options: HashMap::new(),
+		colors: HashSet::new(),
+	};
+	
+	for (name, value) in meta.options.iter().flatten() {
+		match value {
+			metabase::OptionBase::Files(value) => {known.options.insert(name.to_owned(), value.options.iter().flat_map(|v| v.keys().cloned()).collect());}
+			metabase::OptionBase::Color(_) => {known.colors.insert(name.to_owned());}
+			_ => {}
+		}
+	}
+	
+	let svgs = crate::get_svgs(svg_root)?;
+	let count = svgs.len();
+	let mut game_paths = HashMap::<GamePath, Vec<(PathBuf, String)>>::new();
+	for (file, paths) in svgs.into_par_iter().filter_map(|file| {
+		match lint_file(&file, &known, lib, diag) {
+			Ok(v) => Some((file, v)),
+			Err(e) => {
+				diag.push_error(file.to_string_lossy(), e);
+				None
+			}
+		}
+	}).collect::<Vec<_>>() {
+		for (game_path, at) in paths {
+			game_paths.entry(game_path).or_default().push((file.clone(), at));
+		}
+	}
+	
+	// multiple layers of the same file are merged, multiple files overwrite each other
+	for ((game_path, option), locations) in game_paths {
+		if locations.iter().all(|(file, _)| *file == locations[0].0) {continue}
+		
+		let mut diagnostic = Diagnostic::error("Game path is created by multiple files, only one of them will be used").source(&game_path);
+		if let Some((o1, o2)) = option {
+			diagnostic = diagnostic.context("option", format!("{o1}:{o2}"));
+		}
+		
+		for (_, at) in locations {
+			diagnostic = diagnostic.context("at", at);
+		}
+		
+		diag.push(diagnostic);
+	}
+	
+	println!("Linted {count} svgs");
+	
+	Ok(())
+}
+
+fn lint_file(file: &Path, known: &Known, lib: &svg::Library, diag: &Diagnostics) -> Result<Vec<(GamePath, String)>, crate::Error> {
+	let source = file.to_string_lossy();
+	let data = std::fs::read_to_string(file)?;
+	let root = svg::Element::parse(&data, file)?;
+	
+	// uses are resolved against the same ids split_svgs resolves them against
+	let mut ids = lib.ids();
+	ids.extend(root.ids());
+	let mut linter = Linter {
+		file,
+		known,
+		lib,
+		ids,
+		found: Vec::new(),
+		game_paths: Vec::new(),
+		definitions: HashMap::new(),
+		used: HashSet::new(),
+		visited: HashSet::new(),
+	};
+	linter.layers(&root);
+	
+	let Linter{mut found, game_paths, definitions, used, ..} = linter;
+	for (id, diagnostic) in definitions {
+		if !used.contains(id) {
+			found.push(diagnostic);
+		}
+	}
+	
+	// anything else split_svgs fails on, only the first error is returned so it would mostly repeat the ones above
+	if found.iter().all(|v| v.severity == Severity::Warning) {
+		if let Err(e) = crate::split_svgs(&data, file, lib) {
+			diag.push_error(source.clone(), e);
+		}
+	}
+	
+	for diagnostic in found {
+		diag.push(diagnostic.source(source.clone()));
+	}
+	
+	Ok(game_paths)
+}
+
+// walks the layers of an svg the way split_svgs does, collecting every problem instead of stopping at the first one
+struct Linter<'a> {
+	file: &'a Path,
+	known: &'a Known,
+	lib: &'a svg::Library,
+	// of the svg and the library
+	ids: HashMap<&'a str, &'a svg::Element>,
+	found: Vec<Diagnostic>,
+	game_paths: Vec<(GamePath, String)>,
+	// reusable groups by id, with the warning for when they're never used
+	definitions: HashMap<&'a str, Diagnostic>,
+	// ids used by what gets rendered
+	used: HashSet<&'a str>,
+	// elements already linted, as they can be used more than once
+	visited: HashSet<*const svg::Element>,
+}
+
+impl<'a> Linter<'a> {
+	fn error(&mut self, element: &svg::Element, ancestors: &[Ancestor], message: impl Into<String>) {
+		self.found.push(Location{file: self.file, position: element.position, ancestors}.locate(Diagnostic::error(message)));
+	}
+	
+	// whether split_svgs leaves the element out, reusable groups are only rendered where they're used
+	fn skip(&mut self, element: &'a svg::Element, ancestors: &mut Vec<Ancestor>) -> bool {
+		if element.is_reusable() {
+			ancestors.push(Ancestor::new(&element.attributes));
+			match element.id() {
+				Some(id) => {self.definitions.entry(id).or_insert_with(|| Location{file: self.file, position: element.position, ancestors}.locate(Diagnostic::warning("Reusable group is never used")));}
+				None => self.error(element, ancestors, "Reusable group has no id"),
+			}
+			ancestors.pop();
+		}
+		
+		element.is_skipped()
+	}
+	
+	// game path, option and color option layers
+	fn layers(&mut self, root: &'a svg::Element) {
+		let mut ancestors = vec![Ancestor::new(&root.attributes)];
+		for g1 in root.elements() {
+			// anything besides the path layers is defs or inkscape metadata
+			if !g1.is("g") || self.skip(g1, &mut ancestors) {continue}
+			ancestors.push(Ancestor::new(&g1.attributes));
+			let path = match g1.label().map(|v| v.trim()) {
+				Some(label) if label.contains("./") || label.contains(".\\") => {
+					self.error(g1, &ancestors, "Game path label is invalid");
+					None
+				}
+				
+				Some(label) if !label.is_empty() => Some(label.to_ascii_lowercase()),
+				_ => {
+					self.error(g1, &ancestors, "Layer is missing its game path label");
+					None
+				}
+			};
+			
+			if let Some(path) = path {
+				for g2 in g1.elements() {
+					if self.skip(g2, &mut ancestors) {continue}
+					ancestors.push(Ancestor::new(&g2.attributes));
+					if self.is_layer(g2, &ancestors) {
+						self.options(g2, &path, &mut ancestors);
+					}
+					ancestors.pop();
+				}
+			}
+			
+			ancestors.pop();
+		}
+	}
+	
+	// if the element is a layer group, anything else is reference images and such that only matter in inkscape
+	fn is_layer(&mut self, element: &svg::Element, ancestors: &[Ancestor]) -> bool {
+		// used elements have nowhere to go
+		if element.is("use") {
+			self.error(element, ancestors, "Use element is not inside of a color option layer");
+		}
+		
+		element.is("g")
+	}
+	
+	fn options(&mut self, g2: &'a svg::Element, path: &str, ancestors: &mut Vec<Ancestor>) {
+		let location = Location{file: self.file, position: g2.position, ancestors};
+		let Some(label) = g2.label() else {
+			self.error(g2, ancestors, "Layer is missing its option label, use a space for the base files");
+			return;
+		};
+		
+		for option in label.trim().split(';') {
+			if option.trim().is_empty() {
+				self.game_paths.push(((path.to_owned(), None), location.at()));
+				continue;
+			}
+			
+			let Some((o1, o2)) = option.split_once(':') else {
+				self.found.push(location.locate(Diagnostic::error(format!("Option '{option}' is not in the Option:Sub format"))));
+				continue;
+			};
+			
+			match self.known.options.get(o1) {
+				Some(subs) if subs.contains(o2) => self.game_paths.push(((path.to_owned(), Some((o1.to_owned(), o2.to_owned()))), location.at())),
+				Some(_) => self.found.push(location.locate(Diagnostic::error(format!("Option '{o1}' has no sub option '{o2}'")))),
+				None => self.found.push(location.locate(Diagnostic::error(format!("Unknown option '{o1}'")))),
+			}
+		}
+		
+		for g3 in g2.elements() {
+			if self.skip(g3, ancestors) {continue}
+			ancestors.push(Ancestor::new(&g3.attributes));
+			if self.is_layer(g3, ancestors) {
+				self.colors(g3, ancestors);
+			}
+			ancestors.pop();
+		}
+	}
+	
+	fn colors(&mut self, g3: &'a svg::Element, ancestors: &mut Vec<Ancestor>) {
+		match g3.label().map(|v| crate::LayerStyle::parse(v.trim())) {
+			Some(Ok(style)) => for option in style.options().filter(|v| !self.known.colors.contains(*v)) {
+				self.error(g3, ancestors, format!("Unknown color option '{option}'"));
+			}
+			
+			Some(Err(e)) => self.error(g3, ancestors, e),
+			None => self.error(g3, ancestors, "Layer is missing its color option label, use a space for no color"),
+		}
+		
+		self.content(g3, ancestors);
+	}
+	
+	// the children of an element that get rendered
+	fn content(&mut self, element: &'a svg::Element, ancestors: &mut Vec<Ancestor>) {
+		for child in element.elements() {
+			if self.skip(child, ancestors) {continue}
+			ancestors.push(Ancestor::new(&child.attributes));
+			self.resolve(child, ancestors);
+			ancestors.pop();
+		}
+	}
+	
+	// what a use refers to gets linted as if it were in its place, like split_svgs copies it there
+	fn resolve(&mut self, element: &'a svg::Element, ancestors: &mut Vec<Ancestor>) {
+		// once is enough, and uses referring to themselves would never end
+		if !self.visited.insert(element) {return}
+		if !element.is("use") {
+			self.content(element, ancestors);
+			return;
+		}
+		
+		let Some(href) = element.attribute("href") else {
+			self.error(element, ancestors, "Use element has no href");
+			return;
+		};
+		
+		if let Some(reference) = href.strip_prefix("lib:") {
+			if self.lib.find(reference).is_none() {
+				self.error(element, ancestors, format!("Use element refers to {href}, which is not in the library"));
+			}
+			return;
+		}
+		
+		let Some(id) = href.strip_prefix('#') else {
+			self.error(element, ancestors, format!("Use element refers to {href}, which is neither in this svg nor the library"));
+			return;
+		};
+		
+		let Some(target) = self.ids.get(id).copied() else {
+			self.error(element, ancestors, format!("Use element refers to {href}, which does not exist"));
+			return;
+		};
+		
+		self.used.insert(id);
+		self.resolve(target, ancestors);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn known() -> Known {
+		Known {
+			options: HashMap::from([
+				("Shape".to_owned(), HashSet::from(["Square".to_owned(), "Rounded".to_owned(), "Pill".to_owned()])),
+				("Style".to_owned(), HashSet::from(["Faded".to_owned()])),
+			]),
+			colors: ["Primary Color", "Secondary Color", "Accent", "Highlight Opacity", "Fill Amount", "Foreground Color", "Secondary Foreground Color"].into_iter().map(|v| v.to_owned()).collect(),
+		}
+	}
+	
+	// what split_svgs renders lint has to accept
+	#[test]
+	fn fixtures() {
+		let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+		let lib = svg::Library::load(&root.join("lib")).unwrap();
+		for entry in std::fs::read_dir(&root).unwrap() {
+			let path = entry.unwrap().path();
+			if path.extension().is_none_or(|v| v != "svg") {continue}
+			
+			let diag = Diagnostics::default();
+			lint_file(&path, &known(), &lib, &diag).unwrap();
+			assert_eq!(diag.errors(), 0, "{path:?}");
+		}
+	}
+	
+	#[test]
+	fn errors() {
+		let path = std::env::temp_dir().join(format!("preprocessor-lint-{}.svg", std::process::id()));
+		std::fs::write(&path, r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">
+			<g inkscape:label="+Unnamed" />
+			<g inkscape:label="ui/uld/Lint_hr1.tex">
+				<g inkscape:label="Shape:Oval;Size:Large;Shape">
+					<g inkscape:label="Secondary Colour">
+						<use xlink:href="#missing" />
+						<use xlink:href="lib:missing" />
+						<use xlink:href="#guide" />
+					</g>
+				</g>
+				<g inkscape:label="_Guides">
+					<rect id="guide" />
+				</g>
+			</g>
+		</svg>"##).unwrap();
+		
+		let diag = Diagnostics::default();
+		let game_paths = lint_file(&path, &known(), &svg::Library::default(), &diag).unwrap();
+		_ = std::fs::remove_file(&path);
+		// the unnamed group, the 3 options, the color and the 2 missing uses, a use of a _ layer gets rendered
+		assert_eq!(diag.errors(), 7);
+		assert!(game_paths.is_empty());
+	}
+}
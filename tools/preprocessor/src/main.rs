@@ -1,719 +1,1021 @@
-use std::{collections::HashMap, fs::File, io::{BufWriter, Cursor, Write}, path::{Path, PathBuf}};
+use std::{collections::{HashMap, HashSet}, fs::File, io::{BufWriter, Cursor}, path::{Path, PathBuf}};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 // mod meta;
 // mod tex_composite;
+mod artifacts;
+mod bc;
+mod cache;
+mod cli;
+mod composite;
+mod compression;
+mod diagnostics;
+mod flatten;
+mod game;
+#[cfg(test)]
+mod golden;
+mod guidelines;
+mod image_ops;
+mod manifest;
 mod metabase;
+mod mips;
 mod icons;
+mod lint;
+mod previews;
+mod sheets;
+mod svg;
+mod tex;
 mod uld;
+mod watch;
+
+use manifest::Files;
+use diagnostics::{Diagnostic, Diagnostics};
 
 type Error = Box<dyn std::error::Error>;
 
-fn main() -> Result<(), Error> {
-	let args = std::env::args().collect::<Vec<String>>();
-	if args.len() < 3 {
-		println!("Usage: {} <svg_root_dir> <target_dir> <meta.yaml>? <merge_dir>?", std::env::current_exe().unwrap().file_name().unwrap().to_string_lossy());
-		return Ok(());
+fn main() -> std::process::ExitCode {
+	let args = <cli::Args as clap::Parser>::parse();
+	println!("{:?}", args.target);
+	
+	let watch = match args.watch {
+		true => match watch::Stages::of(&args.command) {
+			Some(v) => Some(v),
+			None => {
+				println!("--watch only works with the render, merge, meta and all commands");
+				return std::process::ExitCode::FAILURE;
+			}
+		}
+		
+		false => None,
+	};
+	
+	let diag = Diagnostics::default();
+	build(&args, &diag);
+	diag.report();
+	let failed = diag.errors() > 0;
+	
+	if let Some(stages) = watch {
+		if let Err(e) = watch::watch(&args, stages) {
+			println!("Failed watching: {e}");
+			return std::process::ExitCode::FAILURE;
+		}
 	}
 	
-	let mut files = HashMap::<Option<(String, String)>, HashMap<String, String>>::new();
+	// color paths log
+	// let mut f = BufWriter::new(File::create("color_paths.log")?);
+	// for (color, paths) in color_paths {
+	// 	writeln!(f, "\n{color}")?;
+	// 	let mut paths = paths.into_iter().collect::<Vec<_>>();
+	// 	paths.sort();
+	// 	for p in paths {
+	// 		writeln!(f, "\t{p}")?;
+	// 	}
+	// }
 	
-	// svg renderer
-	let svg_root = Path::new(&args[1]);
-	let target_root = Path::new(&args[2]);
-	println!("{:?}", target_root);
+	if failed {std::process::ExitCode::FAILURE} else {std::process::ExitCode::SUCCESS}
+}
+
+// the command that runs a stage of the build, its name and what it does
+type Stage<'a> = (cli::Command, &'a str, &'a dyn Fn() -> Result<(), Error>);
+
+// runs the requested stages, stopping after the first one with errors unless keep going is set
+fn build(args: &cli::Args, diag: &Diagnostics) {
+	let lib = match svg::Library::load(&args.lib) {
+		Ok(v) => v,
+		Err(e) => {
+			diag.push_error(args.lib.to_string_lossy(), e);
+			return;
+		}
+	};
 	
-	let mut font = resvg::usvg::fontdb::Database::new();
-	font.load_system_fonts();
-	font.load_font_data(include_bytes!("Axis Extrabold.otf").to_vec());
-	font.load_font_data(include_bytes!("Miedinger Bold.otf").to_vec());
+	let game = game::source(args);
+	// checks and tools that don't create any files of the mod
+	let check = match &args.command {
+		cli::Command::Lint => Some(("lint", lint::lint(&args.svg, &lib, &args.meta, diag))),
+		cli::Command::Guidelines => Some(("guidelines", guidelines::check(&args.svg, &lib, diag))),
+		// reads what previous builds created
+		cli::Command::Flatten{preset, out} => Some(("flatten", flatten::flatten(&args.target, &args.meta, preset, out, game.as_ref(), diag))),
+		cli::Command::Sheets{preset, out} => Some(("sheets", sheets::sheets(&args.target, &args.meta, preset.as_deref(), out, game.as_ref(), diag))),
+		_ => None,
+	};
 	
-	fn get_svgs(path: &Path) -> Result<Vec<PathBuf>, Error> {
-		let mut files = Vec::new();
-		for entry in std::fs::read_dir(path)? {
-			let entry_path = entry?.path();
-			if entry_path.is_dir() {
-				files.append(&mut get_svgs(&entry_path)?);
-			} else if entry_path.extension().map(|v| v.to_str()) == Some(Some("svg")) {
-				files.push(entry_path);
-			}
+	if let Some((name, result)) = check {
+		if let Err(e) = result {
+			diag.push_error(format!("<{name}>"), e);
 		}
 		
-		Ok(files)
+		return;
 	}
 	
-	for a in get_svgs(svg_root)?.into_par_iter().map(|path| {
-		let mut files = HashMap::new();
-		let svgs = split_svgs(&std::fs::read_to_string(path).unwrap()).unwrap();
-		for svg in svgs {
-			let local_dir = if let Some((o1, o2)) = &svg.option {
-				format!("{}/{o1}/{o2}", svg.path.clone())
-			} else {
-				svg.path.clone()
-			};
-			
-			let paths = files.entry(svg.option.clone()).or_insert_with(|| HashMap::new());
-			if svg.layers.len() > 1 || svg.layers[0].0 != None {
-				paths.insert(format!("{}.comp", &svg.path), format!("{local_dir}/comp.tex.comp"));
-				// if svg.path.starts_with("ui/uld/") {
-				// 	paths.insert(format!("{}.comp", &svg.path.replace("ui/uld/", "ui/uld/fourth/")), format!("{local_dir}/comp.tex.comp"));
-				// }
-			} else {
-				paths.insert(svg.path.clone(), format!("{local_dir}/0.tex"));
-				// if svg.path.starts_with("ui/uld/") {
-				// 	paths.insert(svg.path.replace("ui/uld/", "ui/uld/fourth/"), format!("{local_dir}/0.tex"));
-				// }
-			}
-			
-			render_svg(svg, &target_root.join("files"), &font).unwrap();
-			// render_svg(svg, &target_root.join("files"), &font, &mut color_paths).unwrap();
+	let tex = match compression::Rules::load(args) {
+		Ok(v) => v,
+		Err(e) => {
+			diag.push_error(args.textures.to_string_lossy(), e);
+			return;
 		}
-		
-		files
-	}).collect::<Vec<_>>() {
-		for (k, v) in a {
-			let b = files.entry(k).or_insert_with(|| HashMap::new());
-			for (g, r) in v {
-				b.insert(g, r);
-			}
+	};
+	
+	// written to the assets instead of the mod, so not part of any build
+	if args.command == cli::Command::Previews {
+		if let Err(e) = previews::previews(&args.svg, &lib, &args.meta, &args.assets, &tex, diag) {
+			diag.push_error("<previews>", e);
 		}
+		
+		return;
 	}
 	
-	// uld
-	let entry = files.entry(None).or_insert_with(|| HashMap::new());
-	for a in uld::ulds(&target_root)? {
-		entry.insert(a.clone(), a);
+	let artifacts = artifacts::Artifacts::new(args);
+	if !artifacts.emit.is_empty() {
+		println!("Writing debug sidecars to {:?}", artifacts.debug_root);
+	}
+	
+	let target_root = args.target.as_path();
+	match artifacts.remove_legacy(target_root) {
+		Ok(0) => {}
+		Ok(count) => println!("Removed {count} debug sidecars earlier builds left in the files directory"),
+		Err(e) => diag.push_error("<debug sidecars>", e),
 	}
 	
-	// std::process::exit(0);
+	let stages: [Stage; 5] = [
+		(cli::Command::Render, "render", &|| manifest::save(target_root, "render", &render(&args.svg, &lib, target_root, !args.no_cache, &tex, &artifacts, diag)?)),
+		(cli::Command::Uld, "uld", &|| manifest::save(target_root, "uld", &ulds(target_root, game.as_ref())?)),
+		(cli::Command::Icons, "icons", &|| manifest::save(target_root, "icons", &icons::icons(&args.icons, &args.meta, game.as_ref(), target_root, &tex, &artifacts, diag)?)),
+		(cli::Command::Merge, "merge", &|| manifest::save(target_root, "merge", &merge(&args.merge, target_root)?)),
+		(cli::Command::Meta, "meta", &|| meta(&args.meta, &manifest::load(target_root)?, diag)),
+	];
 	
-	// icons
-	for ((o, so), f) in icons::job_icons(&target_root)? {
-		let paths = files.entry(Some((o.to_owned(), so.to_owned()))).or_insert_with(|| HashMap::new());
-		for (a, b) in f {
-			paths.insert(a, b);
+	for (command, name, stage) in stages {
+		if args.command != command && args.command != cli::Command::All {continue}
+		
+		if let Err(e) = stage() {
+			diag.push_error(format!("<{name} stage>"), e);
+		}
+		
+		if diag.errors() > 0 && !args.keep_going {
+			println!("Stopping after errors in the {name} stage, use --keep-going to run the remaining stages anyway");
+			return;
+		}
+	}
+}
+
+fn load_fonts() -> resvg::usvg::fontdb::Database {
+	let mut font = resvg::usvg::fontdb::Database::new();
+	font.load_system_fonts();
+	font.load_font_data(include_bytes!("Axis Extrabold.otf").to_vec());
+	font.load_font_data(include_bytes!("Miedinger Bold.otf").to_vec());
+	font
+}
+
+fn get_svgs(path: &Path) -> Result<Vec<PathBuf>, Error> {
+	let mut files = Vec::new();
+	for entry in std::fs::read_dir(path)? {
+		let entry_path = entry?.path();
+		if entry_path.is_dir() {
+			files.append(&mut get_svgs(&entry_path)?);
+		} else if entry_path.extension().map(|v| v.to_str()) == Some(Some("svg")) {
+			files.push(entry_path);
 		}
 	}
 	
-	let entry = files.entry(None).or_insert_with(|| HashMap::new());
-	for (a, b) in icons::tribe_icons(&target_root)? {
-		entry.insert(a, b);
+	Ok(files)
+}
+
+fn render(svg_root: &Path, lib: &svg::Library, target_root: &Path, use_cache: bool, tex: &compression::Rules, artifacts: &artifacts::Artifacts, diag: &Diagnostics) -> Result<Files, Error> {
+	let mut files = Files::new();
+	let files_root = target_root.join("files");
+	let old_cache = if use_cache {cache::Cache::load(target_root)} else {cache::Cache::default()};
+	let mut new_cache = cache::Cache::default();
+	let font = load_fonts();
+	
+	let results = get_svgs(svg_root)?.into_par_iter().map(|path| {
+		match render_file(&path, &files_root, &font, lib, &old_cache, tex, artifacts) {
+			Ok(v) => Some(v),
+			Err(e) => {
+				diag.push_error(path.to_string_lossy(), e);
+				None
+			}
+		}
+	}).collect::<Vec<_>>();
+	
+	let failed = results.iter().any(|v| v.is_none());
+	for (a, entries) in results.into_iter().flatten() {
+		manifest::merge(&mut files, a);
+		new_cache.entries.extend(entries);
 	}
 	
-	let entry = files.entry(None).or_insert_with(|| HashMap::new());
-	for (a, b) in icons::silver_bordered(&target_root)? {
-		entry.insert(a, b);
+	let rendered = new_cache.entries.keys().filter(|k| old_cache.entries.get(*k).is_none_or(|v| v.hash != new_cache.entries[*k].hash)).count();
+	let outputs = new_cache.entries.len();
+	// which outputs belonged to an svg that failed is unknown, so they all stay until a run where everything renders
+	let pruned = if failed {
+		new_cache.keep(&old_cache);
+		0
+	} else {
+		old_cache.prune(&files_root, &new_cache, artifacts)?
+	};
+	println!("Rendered {rendered} of {outputs} outputs, pruned {pruned} stale files");
+	new_cache.save(target_root)?;
+	
+	Ok(files)
+}
+
+// splits and renders a single svg, returning the files it provides and the cache entries of its outputs
+fn render_file(path: &Path, files_root: &Path, font: &resvg::usvg::fontdb::Database, lib: &svg::Library, cache: &cache::Cache, tex: &compression::Rules, artifacts: &artifacts::Artifacts) -> Result<(Files, Vec<(String, cache::Entry)>), Error> {
+	let mut files = HashMap::new();
+	let mut entries = Vec::new();
+	let mut svgs = Vec::new();
+	for svg in split_svgs(&std::fs::read_to_string(path)?, path, lib)? {
+		// rendered from the vector again at half scale, instead of downscaling the hr1 bitmap
+		if let Some(low_path) = tex.low_res_path(&svg.path) {
+			svgs.push((SvgResult{path: low_path, ..svg.clone()}, 0.5));
+		}
+		
+		svgs.push((svg, 1.0));
 	}
 	
-	let entry = files.entry(None).or_insert_with(|| HashMap::new());
-	for (a, b) in icons::shop_icons(&target_root)? {
-		entry.insert(a, b);
+	for (svg, scale) in svgs {
+		let local_dir = if let Some((o1, o2)) = &svg.option {
+			format!("{}/{o1}/{o2}", svg.path.clone())
+		} else {
+			svg.path.clone()
+		};
+		
+		let paths = files.entry(svg.option.clone()).or_insert_with(|| HashMap::new());
+		if svg.is_composite() {
+			paths.insert(format!("{}.comp", &svg.path), format!("{local_dir}/comp.tex.comp"));
+			// if svg.path.starts_with("ui/uld/") {
+			// 	paths.insert(format!("{}.comp", &svg.path.replace("ui/uld/", "ui/uld/fourth/")), format!("{local_dir}/comp.tex.comp"));
+			// }
+		} else {
+			paths.insert(svg.path.clone(), format!("{local_dir}/0.tex"));
+			// if svg.path.starts_with("ui/uld/") {
+			// 	paths.insert(svg.path.replace("ui/uld/", "ui/uld/fourth/"), format!("{local_dir}/0.tex"));
+			// }
+		}
+		
+		entries.append(&mut render_svg(svg, scale, files_root, font, cache, tex, artifacts)?);
+		// render_svg(svg, &target_root.join("files"), &font, &mut color_paths).unwrap();
 	}
 	
+	Ok((files, entries))
+}
+
+fn ulds(target_root: &Path, game: &dyn game::Source) -> Result<Files, Error> {
+	let mut files = Files::new();
 	let entry = files.entry(None).or_insert_with(|| HashMap::new());
-	for (a, b) in icons::menu_icons(&target_root)? {
-		entry.insert(a, b);
-	}
-	
-	// raw static files
-	if args.len() >= 5 {
-		fn walk_dir2(path: &Path, path_rel: String, target: &Path, files: &mut HashMap<Option<(String, String)>, HashMap<String, String>>) -> Result<(), Error> {
-			for entry in std::fs::read_dir(path)? {
-				let entry_path = entry?.path();
-				let filename = entry_path.file_name().unwrap().to_string_lossy().to_string();
-				if entry_path.is_dir() {
-					if filename.contains(".") {
-						for opt_entry in std::fs::read_dir(entry_path)? {
-							let opt_entry_path = opt_entry?.path();
-							let opt_name = opt_entry_path.file_name().unwrap().to_string_lossy().to_string();
-							for sub_entry in std::fs::read_dir(opt_entry_path)? {
-								let sub_entry_path = sub_entry?.path();
-								let sub_name = sub_entry_path.file_name().unwrap().to_string_lossy().to_string();
-								let paths = files.entry(Some((opt_name.to_string(), sub_name.to_string()))).or_insert_with(|| HashMap::new());
+	for a in uld::ulds(game, target_root)? {
+		entry.insert(a.clone(), a);
+	}
+	
+	Ok(files)
+}
+
+// raw static files
+fn merge(merge_root: &Path, target_root: &Path) -> Result<Files, Error> {
+	fn walk_dir2(path: &Path, path_rel: String, target: &Path, files: &mut Files) -> Result<(), Error> {
+		for entry in std::fs::read_dir(path)? {
+			let entry_path = entry?.path();
+			let filename = entry_path.file_name().unwrap().to_string_lossy().to_string();
+			if entry_path.is_dir() {
+				if filename.contains(".") {
+					for opt_entry in std::fs::read_dir(entry_path)? {
+						let opt_entry_path = opt_entry?.path();
+						let opt_name = opt_entry_path.file_name().unwrap().to_string_lossy().to_string();
+						for sub_entry in std::fs::read_dir(opt_entry_path)? {
+							let sub_entry_path = sub_entry?.path();
+							let sub_name = sub_entry_path.file_name().unwrap().to_string_lossy().to_string();
+							let paths = files.entry(Some((opt_name.to_string(), sub_name.to_string()))).or_insert_with(|| HashMap::new());
+							
+							for file_entry in std::fs::read_dir(sub_entry_path)? {
+								let file_entry_path = file_entry?.path();
+								let filename = file_entry_path.file_name().unwrap().to_string_lossy().to_string();
 								
-								for file_entry in std::fs::read_dir(sub_entry_path)? {
-									let file_entry_path = file_entry?.path();
-									let filename = file_entry_path.file_name().unwrap().to_string_lossy().to_string();
-									
-									let new_dir = target.join(&path_rel).join(&filename).join(&opt_name).join(&sub_name);
-									_ = std::fs::create_dir_all(&new_dir);
-									std::fs::copy(file_entry_path, new_dir.join(&filename))?;
-									paths.insert(format!("{path_rel}{filename}"), format!("{path_rel}{filename}/{opt_name}/{sub_name}/{filename}"));
-								}
+								let new_dir = target.join(&path_rel).join(&filename).join(&opt_name).join(&sub_name);
+								_ = std::fs::create_dir_all(&new_dir);
+								std::fs::copy(file_entry_path, new_dir.join(&filename))?;
+								paths.insert(format!("{path_rel}{filename}"), format!("{path_rel}{filename}/{opt_name}/{sub_name}/{filename}"));
 							}
 						}
-					} else {
-						walk_dir2(&entry_path, format!("{path_rel}{}/", filename), target, files)?;
 					}
 				} else {
-					_ = std::fs::create_dir_all(&target.join(&path_rel));
-					std::fs::copy(entry_path, target.join(&path_rel).join(&filename))?;
-					let paths = files.entry(None).or_insert_with(|| HashMap::new());
-					let path = format!("{path_rel}{filename}");
-					paths.insert(path.clone(), path);
+					walk_dir2(&entry_path, format!("{path_rel}{}/", filename), target, files)?;
 				}
+			} else {
+				_ = std::fs::create_dir_all(&target.join(&path_rel));
+				std::fs::copy(entry_path, target.join(&path_rel).join(&filename))?;
+				let paths = files.entry(None).or_insert_with(|| HashMap::new());
+				let path = format!("{path_rel}{filename}");
+				paths.insert(path.clone(), path);
 			}
-			
-			Ok(())
 		}
 		
-		walk_dir2(Path::new(&Path::new(&args[4])), String::new(), &target_root.join("files"), &mut files)?;
+		Ok(())
 	}
 	
-	// meta file creation
+	let mut files = Files::new();
+	walk_dir2(merge_root, String::new(), &target_root.join("files"), &mut files)?;
+	
+	Ok(files)
+}
+
+// meta file creation
+fn meta(meta_path: &Path, files: &Files, diag: &Diagnostics) -> Result<(), Error> {
 	use aetherment::modman::{meta, requirement::Requirement, settings::*};
 	
-	if args.len() >= 4 {
-		let meta_base = serde_yaml::from_slice::<metabase::MetaBase>(&std::fs::read(&args[3])?)?;
+	let source = meta_path.to_string_lossy();
+	// errors of earlier stages when running with --keep-going
+	let errors = diag.errors();
+	let meta_base = metabase::MetaBase::load(meta_path)?;
+	
+	let mut option_indexes = HashMap::new();
+	let options = meta_base.options.into_iter().filter_map(|o| {
+		let name = o.keys().next().unwrap().to_owned();
+		let value = o.values().next().unwrap().to_owned();
 		
-		let mut option_indexes = HashMap::new();
-		let options = meta_base.options.into_iter().map(|o| {
-			let name = o.keys().next().unwrap().to_owned();
-			let value = o.values().next().unwrap().to_owned();
+		Some(match value {
+			metabase::OptionBase::Category(_) => meta::OptionType::Category(name.to_owned()),
 			
-			match value {
-				metabase::OptionBase::Category(_) => meta::OptionType::Category(name.to_owned()),
-				
-				metabase::OptionBase::Files(value) => {
-					option_indexes.insert(name.to_owned(), value.options.iter().enumerate().map(|(i, v)| (v.keys().next().unwrap().to_owned(), i)).collect::<HashMap<_, _>>());
-					
-					meta::OptionType::Option(meta::Option {
-						name: name.to_owned(),
-						description: value.description.to_owned(),
-						settings: meta::OptionSettings::SingleFiles(meta::ValueFiles {
-							default: value.default.as_ref().map_or(0, |v| value.options.iter().position(|v2| v2.keys().next().unwrap() == v).map_or(0, |v| v as u32)),
-							options: value.options.iter().map(|sub_value| {
-								let sub_name = sub_value.keys().next().unwrap();
-								let sub_value = sub_value.values().next().unwrap();
-								
-								let key = Some((name.to_owned(), sub_name.to_owned()));
-								if !files.contains_key(&key) {
-									println!("No files exist with option {name}:{sub_name}");
-									std::process::exit(0);
-								}
-								
-								meta::ValueFilesOption {
-									name: sub_name.to_owned(),
-									description: sub_value.description.to_owned(),
-									inherit: sub_value.inherit.as_ref().map(|v| v.to_owned()),
-									files: files[&key].clone(),
-									
-									..Default::default()
-								}
-							}).collect(),
-						})
-					})
-				}
+			metabase::OptionBase::Files(value) => {
+				option_indexes.insert(name.to_owned(), value.options.iter().enumerate().map(|(i, v)| (v.keys().next().unwrap().to_owned(), i)).collect::<HashMap<_, _>>());
 				
-				metabase::OptionBase::Color(color) => {
-					let default = &color.default;
-					let min = &color.min;
-					let max = &color.max;
-					
-					meta::OptionType::Option(meta::Option {
-						name: name.to_owned(),
-						description: color.description,
-						settings: match default.len() {
-							4 => meta::OptionSettings::Rgba(meta::ValueRgba {
-								default: default[..].try_into().unwrap(),
-								min: min[..].try_into().unwrap(),
-								max: max[..].try_into().unwrap(),
-							}),
+				meta::OptionType::Option(meta::Option {
+					name: name.to_owned(),
+					description: value.description.to_owned(),
+					settings: meta::OptionSettings::SingleFiles(meta::ValueFiles {
+						default: value.default.as_ref().map_or(0, |v| value.options.iter().position(|v2| v2.keys().next().unwrap() == v).map_or(0, |v| v as u32)),
+						options: value.options.iter().map(|sub_value| {
+							let sub_name = sub_value.keys().next().unwrap();
+							let sub_value = sub_value.values().next().unwrap();
 							
-							3 => meta::OptionSettings::Rgb(meta::ValueRgb {
-								default: default[..].try_into().unwrap(),
-								min: min[..].try_into().unwrap(),
-								max: max[..].try_into().unwrap(),
-							}),
-							
-							1 => meta::OptionSettings::Grayscale(meta::ValueSingle {
-								default: default[0],
-								min: min[0],
-								max: max[0],
-							}),
+							let key = Some((name.to_owned(), sub_name.to_owned()));
+							if !files.contains_key(&key) {
+								diag.push(Diagnostic::error("No files exist with option").source(source.clone()).context("option", format!("{name}:{sub_name}")));
+							}
 							
-							_ => panic!("Unsupported color type")
-						}
-					})
-				}
-				
-				metabase::OptionBase::Grouped(group) => {
-					meta::OptionType::Option(meta::Option {
-						name: name.to_owned(),
-						description: String::new(),
-						settings: meta::OptionSettings::Grouped(meta::ValueGrouped {
-							default: group.default.as_ref().map_or(0, |v| group.options.iter().position(|v2| v2.keys().next().unwrap() == v).map_or(0, |v| v as u32)),
-							options: group.options.iter().map(|v| {
-								meta::ValueGroupedOption {
-									name: v.keys().next().unwrap().to_owned(),
-									description: String::new(),
-									options: v.values().next().unwrap().into_iter().map(|v| {
-										let name = v.keys().next().unwrap().to_owned();
-										match v.values().next().unwrap().to_owned() {
-											metabase::GroupedTypeBase::Category(_) => meta::ValueGroupedOptionEntryType::Category(name),
-											metabase::GroupedTypeBase::Option(options) => meta::ValueGroupedOptionEntryType::Option(meta::ValueGroupedOptionEntry {
-												name,
-												description: String::new(),
-												options,
-											}),
-										}
-									}).collect(),
-								}
-							}).collect(),
-						})
+							meta::ValueFilesOption {
+								name: sub_name.to_owned(),
+								description: sub_value.description.to_owned(),
+								inherit: sub_value.inherit.as_ref().map(|v| v.to_owned()),
+								files: files.get(&key).cloned().unwrap_or_default(),
+								
+								..Default::default()
+							}
+						}).collect(),
 					})
-				}
+				})
 			}
-		}).collect();
-		
-		let meta = meta::Meta {
-			name: meta_base.name,
-			description: meta_base.description,
-			version: meta_base.version,
-			author: meta_base.author,
-			website: meta_base.website,
-			tags: meta_base.tags,
-			dependencies: meta_base.dependencies,
 			
-			presets: meta_base.presets.into_iter().map(|p| {
-				Preset {
-					name: p.keys().next().unwrap().to_owned(),
-					settings: p.values().next().unwrap().into_iter().map(|(o, v)| (o.to_owned(), match v {
-						metabase::ValueBase::Files(v) => Value::SingleFiles(option_indexes[o][v] as u32),
-						metabase::ValueBase::Color(v) => match v.len() {
-							4 => Value::Rgba(v[..].try_into().unwrap()),
-							3 => Value::Rgb(v[..].try_into().unwrap()),
-							_ => panic!("Unsupported color type")
-						},
-					})).collect(),
+			metabase::OptionBase::Color(color) => {
+				let default = &color.default;
+				let min = &color.min;
+				let max = &color.max;
+				if !matches!(default.len(), 1 | 3 | 4) || min.len() != default.len() || max.len() != default.len() {
+					diag.push(Diagnostic::error("Unsupported color type, default, min and max should all have 1, 3 or 4 values").source(source.clone()).context("option", name));
+					return None;
 				}
-			}).collect(),
-			
-			options: meta::Options(options),
-			
-			files: files.get(&None).map_or_else(|| HashMap::new(), |v| v.clone()),
-			
-			// ui_colors: meta_base.colors.into_iter().map(|p| meta::UiColor {
-			// 	use_theme: true,
-			// 	index: *p.keys().next().unwrap(),
-			// 	color: p.values().next().unwrap().to_owned().convert(),
-			// }).collect::<Vec<_>>(),
-			ui_colors: meta_base.colors.into_iter().map(|(index, color)| meta::UiColor {
-				use_theme: index < 1000,
-				index,
-				color: color.convert(),
-			}).collect::<Vec<_>>(),
-			
-			plugin_settings: meta::PluginSettings {
-				dalamud: Some({
-					let s = meta_base.style.variables;
-					meta::dalamud::Style {
-						alpha: s.alpha.convert(),
-						window_padding: s.window_padding.convert(),
-						window_rounding: s.window_rounding.convert(),
-						window_border_size: s.window_border_size.convert(),
-						window_title_align: s.window_title_align.convert(),
-						window_menu_button_position: s.window_menu_button_position.convert(),
-						child_rounding: s.child_rounding.convert(),
-						child_border_size: s.child_border_size.convert(),
-						popup_rounding: s.popup_rounding.convert(),
-						popup_border_size: s.popup_border_size.convert(),
-						frame_padding: s.frame_padding.convert(),
-						frame_rounding: s.frame_rounding.convert(),
-						frame_border_size: s.frame_border_size.convert(),
-						item_spacing: s.item_spacing.convert(),
-						item_inner_spacing: s.item_inner_spacing.convert(),
-						cell_padding: s.cell_padding.convert(),
-						touch_extra_padding: s.touch_extra_padding.convert(),
-						indent_spacing: s.indent_spacing.convert(),
-						scrollbar_size: s.scrollbar_size.convert(),
-						scrollbar_rounding: s.scrollbar_rounding.convert(),
-						grab_min_size: s.grab_min_size.convert(),
-						grab_rounding: s.grab_rounding.convert(),
-						log_slider_deadzone: s.log_slider_deadzone.convert(),
-						tab_rounding: s.tab_rounding.convert(),
-						tab_border_size: s.tab_border_size.convert(),
-						button_text_align: s.button_text_align.convert(),
-						selectable_text_align: s.selectable_text_align.convert(),
-						display_safe_area_padding: s.display_safe_area_padding.convert(),
-						colors: meta_base.style.colors.into_iter().map(|(v, k)| (v, k.convert())).collect(),
+				
+				meta::OptionType::Option(meta::Option {
+					name: name.to_owned(),
+					description: color.description,
+					settings: match default.len() {
+						4 => meta::OptionSettings::Rgba(meta::ValueRgba {
+							default: default[..].try_into().unwrap(),
+							min: min[..].try_into().unwrap(),
+							max: max[..].try_into().unwrap(),
+						}),
+						
+						3 => meta::OptionSettings::Rgb(meta::ValueRgb {
+							default: default[..].try_into().unwrap(),
+							min: min[..].try_into().unwrap(),
+							max: max[..].try_into().unwrap(),
+						}),
+						
+						1 if color.opacity => meta::OptionSettings::Opacity(meta::ValueSingle {
+							default: default[0],
+							min: min[0],
+							max: max[0],
+						}),
+						
+						1 => meta::OptionSettings::Grayscale(meta::ValueSingle {
+							default: default[0],
+							min: min[0],
+							max: max[0],
+						}),
+						
+						_ => unreachable!(),
 					}
 				})
-			},
+			}
 			
-			requirements: vec![
-				Requirement::UiTheme("Dark".to_string()),
-				Requirement::UiResolution("High".to_string()),
-				Requirement::Collection("Interface".to_string()),
-			],
+			metabase::OptionBase::Grouped(group) => {
+				meta::OptionType::Option(meta::Option {
+					name: name.to_owned(),
+					description: String::new(),
+					settings: meta::OptionSettings::Grouped(meta::ValueGrouped {
+						default: group.default.as_ref().map_or(0, |v| group.options.iter().position(|v2| v2.keys().next().unwrap() == v).map_or(0, |v| v as u32)),
+						options: group.options.iter().map(|v| {
+							meta::ValueGroupedOption {
+								name: v.keys().next().unwrap().to_owned(),
+								description: String::new(),
+								options: v.values().next().unwrap().into_iter().map(|v| {
+									let name = v.keys().next().unwrap().to_owned();
+									match v.values().next().unwrap().to_owned() {
+										metabase::GroupedTypeBase::Category(_) => meta::ValueGroupedOptionEntryType::Category(name),
+										metabase::GroupedTypeBase::Option(options) => meta::ValueGroupedOptionEntryType::Option(meta::ValueGroupedOptionEntry {
+											name,
+											description: String::new(),
+											options,
+										}),
+									}
+								}).collect(),
+							}
+						}).collect(),
+					})
+				})
+			}
 			
-			..Default::default()
-		};
+			// replaced by the options it stands for on load
+			metabase::OptionBase::Jobs(_) => return None,
+		})
+	}).collect();
+	
+	let meta = meta::Meta {
+		name: meta_base.name,
+		description: meta_base.description,
+		version: meta_base.version,
+		author: meta_base.author,
+		website: meta_base.website,
+		tags: meta_base.tags,
+		dependencies: meta_base.dependencies,
 		
-		for (option, paths) in &files {
-			if let Some((main, sub)) = option {
-				let Some(opt) = meta.options.0.iter().find(|v| if let meta::OptionType::Option(v) = v {v.name == *main} else {false}) else {
-					println!("No option exists with name {main}");
-					for (p, _) in paths {
-						println!("\t - {p}");
-					}
+		presets: meta_base.presets.into_iter().map(|p| {
+			let name = p.keys().next().unwrap().to_owned();
+			Preset {
+				settings: p.values().next().unwrap().into_iter().filter_map(|(o, v)| Some((o.to_owned(), match v {
+					metabase::ValueBase::Files(v) => match option_indexes.get(o).and_then(|sub| sub.get(v)) {
+						Some(index) => Value::SingleFiles(*index as u32),
+						None => {
+							diag.push(Diagnostic::error("Preset uses an option that does not exist").source(source.clone()).context("preset", &name).context("option", format!("{o}:{v}")));
+							return None;
+						}
+					},
 					
-					continue;
-				};
-				
-				if let meta::OptionType::Option(opt) = opt {
-					if let meta::OptionSettings::SingleFiles(sub_opt) = &opt.settings {
-						if !sub_opt.options.iter().any(|v| v.name == *sub) {
-							println!("No sub option exists for {main} with name {sub}");
-							for (p, _) in paths {
-								println!("\t - {p}");
-							}
+					metabase::ValueBase::Color(v) => match v.len() {
+						4 => Value::Rgba(v[..].try_into().unwrap()),
+						3 => Value::Rgb(v[..].try_into().unwrap()),
+						_ => {
+							diag.push(Diagnostic::error("Unsupported color type, should have 3 or 4 values").source(source.clone()).context("preset", &name).context("option", o));
+							return None;
 						}
+					},
+				}))).collect(),
+				name,
+			}
+		}).collect(),
+		
+		options: meta::Options(options),
+		
+		files: files.get(&None).map_or_else(|| HashMap::new(), |v| v.clone()),
+		
+		// ui_colors: meta_base.colors.into_iter().map(|p| meta::UiColor {
+		// 	use_theme: true,
+		// 	index: *p.keys().next().unwrap(),
+		// 	color: p.values().next().unwrap().to_owned().convert(),
+		// }).collect::<Vec<_>>(),
+		ui_colors: meta_base.colors.into_iter().map(|(index, color)| meta::UiColor {
+			use_theme: index < 1000,
+			index,
+			color: color.convert(),
+		}).collect::<Vec<_>>(),
+		
+		plugin_settings: meta::PluginSettings {
+			dalamud: Some({
+				let s = meta_base.style.variables;
+				meta::dalamud::Style {
+					alpha: s.alpha.convert(),
+					window_padding: s.window_padding.convert(),
+					window_rounding: s.window_rounding.convert(),
+					window_border_size: s.window_border_size.convert(),
+					window_title_align: s.window_title_align.convert(),
+					window_menu_button_position: s.window_menu_button_position.convert(),
+					child_rounding: s.child_rounding.convert(),
+					child_border_size: s.child_border_size.convert(),
+					popup_rounding: s.popup_rounding.convert(),
+					popup_border_size: s.popup_border_size.convert(),
+					frame_padding: s.frame_padding.convert(),
+					frame_rounding: s.frame_rounding.convert(),
+					frame_border_size: s.frame_border_size.convert(),
+					item_spacing: s.item_spacing.convert(),
+					item_inner_spacing: s.item_inner_spacing.convert(),
+					cell_padding: s.cell_padding.convert(),
+					touch_extra_padding: s.touch_extra_padding.convert(),
+					indent_spacing: s.indent_spacing.convert(),
+					scrollbar_size: s.scrollbar_size.convert(),
+					scrollbar_rounding: s.scrollbar_rounding.convert(),
+					grab_min_size: s.grab_min_size.convert(),
+					grab_rounding: s.grab_rounding.convert(),
+					log_slider_deadzone: s.log_slider_deadzone.convert(),
+					tab_rounding: s.tab_rounding.convert(),
+					tab_border_size: s.tab_border_size.convert(),
+					button_text_align: s.button_text_align.convert(),
+					selectable_text_align: s.selectable_text_align.convert(),
+					display_safe_area_padding: s.display_safe_area_padding.convert(),
+					colors: meta_base.style.colors.into_iter().map(|(v, k)| (v, k.convert())).collect(),
+				}
+			})
+		},
+		
+		requirements: vec![
+			Requirement::UiTheme("Dark".to_string()),
+			Requirement::UiResolution("High".to_string()),
+			Requirement::Collection("Interface".to_string()),
+		],
+		
+		..Default::default()
+	};
+	
+	for (option, paths) in files {
+		if let Some((main, sub)) = option {
+			let Some(opt) = meta.options.0.iter().find(|v| if let meta::OptionType::Option(v) = v {v.name == *main} else {false}) else {
+				let mut paths = paths.keys().map(|v| v.as_str()).collect::<Vec<_>>();
+				paths.sort();
+				diag.push(Diagnostic::warning(format!("Files exist for an option that doesn't, they won't be used\n\t\t\t{}", paths.join("\n\t\t\t"))).source(source.clone()).context("option", main));
+				continue;
+			};
+			
+			if let meta::OptionType::Option(opt) = opt {
+				if let meta::OptionSettings::SingleFiles(sub_opt) = &opt.settings {
+					if !sub_opt.options.iter().any(|v| v.name == *sub) {
+						let mut paths = paths.keys().map(|v| v.as_str()).collect::<Vec<_>>();
+						paths.sort();
+						diag.push(Diagnostic::warning(format!("Files exist for a sub option that doesn't, they won't be used\n\t\t\t{}", paths.join("\n\t\t\t"))).source(source.clone()).context("option", format!("{main}:{sub}")));
 					}
 				}
 			}
 		}
-		
-		std::fs::write(Path::new(&args[3]).with_extension("json"), serde_json::to_vec(&meta)?)?;
 	}
 	
-	// color paths log
-	// let mut f = BufWriter::new(File::create("color_paths.log")?);
-	// for (color, paths) in color_paths {
-	// 	writeln!(f, "\n{color}")?;
-	// 	let mut paths = paths.into_iter().collect::<Vec<_>>();
-	// 	paths.sort();
-	// 	for p in paths {
-	// 		writeln!(f, "\t{p}")?;
-	// 	}
-	// }
+	// whatever had errors was left out, the mod would load without it instead of failing
+	if diag.errors() > errors {
+		return Err("meta.json was not written because of the errors above".into());
+	}
+	
+	std::fs::write(meta_path.with_extension("json"), serde_json::to_vec(&meta)?)?;
 	
 	Ok(())
 }
 
+#[derive(Clone)]
 struct SvgResult {
 	path: String,
 	option: Option<(String, String)>,
-	layers: Vec<(Option<String>, String)>,
+	layers: Vec<(LayerStyle, String)>,
 }
 
-fn split_svgs(data: &str) -> Result<Vec<SvgResult>, Error> {
-	// let svg = xml::EventWriter::new(Cursor::new(Vec::new()));
-	let mut svgs: HashMap<String, HashMap<String, Vec<(String, xml::EventWriter<Cursor<Vec<u8>>>)>>> = HashMap::new();
-	let mut layer = 0;
-	let mut root_attributes = Vec::new();
-	let mut path = String::new();
-	let mut option = String::new();
-	let mut color_option = String::new();
-	let mut g1 = Vec::new();
-	let mut g2 = Vec::new();
-	
-	let xml_reader = xml::EventReader::from_str(data);
-	let mut xml = Vec::new();
-	for e in xml_reader {
-		if let Ok(e) = e {
-			match &e {
-				xml::reader::XmlEvent::StartElement{name, attributes, ..} => {
-					if layer == 0 && name.local_name.as_str() == "svg" {
-						root_attributes = attributes.to_owned();
-					}
-					
-					xml.push(e)
-				}
-				
-				xml::reader::XmlEvent::EndElement{..} => xml.push(e),
-				xml::reader::XmlEvent::CData(_) => xml.push(e),
-				xml::reader::XmlEvent::Characters(_) => xml.push(e),
-				
-				_ => {}
-			}
+impl SvgResult {
+	// a single layer without anything to apply to it is saved as a plain texture
+	fn is_composite(&self) -> bool {
+		self.layers.len() > 1 || !self.layers[0].0.is_plain()
+	}
+}
+
+// how a layer gets composited, from its color option label: Color|blend|opacity, everything after the color being optional
+// "Accent|multiply|0.6", "|screen|Glass Opacity" (an opacity option) or just "Accent"
+// the color can be a gradient between two options, "Foreground Color>Secondary Foreground Color@0.3" being 30% of the way to the second one
+// "|mask" makes the layer the alpha mask of the others, culling them where it's darker than 0.5 or the value that follows ("|mask|Fill Amount")
+#[derive(Debug, Clone)]
+struct LayerStyle {
+	color: Option<String>,
+	gradient: Option<(String, f32)>,
+	blend: aetherment::modman::composite::tex::Blend,
+	opacity: Option<Factor>,
+	// the cull point, for mask layers
+	mask: Option<Factor>,
+}
+
+// a value between 0 and 1, static or from a single value color option
+#[derive(Debug, Clone)]
+enum Factor {
+	Static(f32),
+	Option(String),
+}
+
+impl Factor {
+	fn parse(value: &str) -> Result<Self, String> {
+		match value.parse::<f32>() {
+			Ok(v) if (0.0..=1.0).contains(&v) => Ok(Factor::Static(v)),
+			Ok(v) => Err(format!("Value {v} is not between 0 and 1")),
+			Err(_) if value.is_empty() => Err("Layer label has an empty part".to_owned()),
+			Err(_) => Ok(Factor::Option(value.to_owned())),
 		}
 	}
 	
-	let mut force_add = false;
-	let mut force_add_layer = 0;
-	let mut is_use = None;
-	let mut add = Vec::new();
-	let mut use_adds = HashMap::new();
-	for e in xml.iter() {
-		match e {
-			xml::reader::XmlEvent::StartElement{name, attributes, namespace} => {
-				let label = attributes.iter().find(|&v| v.name.local_name == "label");
-				let p = label.map_or(false, |v| v.value.starts_with("+"));
-				if !force_add && ((layer == 1 && name.local_name.as_str() == "defs") || p)  {
-					force_add = true;
-					force_add_layer = layer;
-					is_use = if p {Some(attributes.iter().find(|&v| v.name.local_name == "id").unwrap().to_owned().value)} else {None};
-				}
+	fn option(&self) -> Option<&str> {
+		match self {
+			Factor::Option(v) => Some(v),
+			Factor::Static(_) => None,
+		}
+	}
+}
+
+impl LayerStyle {
+	fn parse(label: &str) -> Result<Self, String> {
+		let mut parts = label.split('|').map(|v| v.trim());
+		let color = parts.next().unwrap_or("");
+		let (color, gradient) = match color.split_once('>') {
+			Some((from, to)) => {
+				let Some((to, position)) = to.rsplit_once('@') else {
+					return Err(format!("Gradient '{color}' is missing its position, like Foreground Color>Secondary Foreground Color@0.5"));
+				};
 				
-				if let Some(use_id) = &is_use {
-					let add = use_adds.entry(use_id.clone()).or_insert_with(|| Vec::new());
-					add.push(xml::writer::XmlEvent::StartElement {
-						name: name.borrow(),
-						namespace: namespace.borrow(),
-						attributes: if layer == force_add_layer {
-							attributes.iter().map(|v| v.borrow()).filter(|v| v.name.local_name != "style").collect()
-						} else {
-							attributes.iter().map(|v| v.borrow()).collect()
-						},
-					})
-				} else if force_add {
-					add.push(xml::writer::XmlEvent::StartElement {
-						name: name.borrow(),
-						namespace: namespace.borrow(),
-						attributes: attributes.iter().map(|v| v.borrow()).collect(),
-					})
-				}
+				let Ok(Factor::Static(position)) = Factor::parse(position.trim()) else {
+					return Err(format!("Gradient position '{}' is not a number between 0 and 1", position.trim()));
+				};
 				
-				layer += 1;
+				(from.trim(), Some((to.trim().to_owned(), position)))
 			}
 			
-			xml::reader::XmlEvent::EndElement{name} => {
-				if let Some(use_id) = &is_use {
-					let add = use_adds.entry(use_id.clone()).or_insert_with(|| Vec::new());
-					add.push(xml::writer::XmlEvent::EndElement {
-						name: Some(name.borrow()),
-					})
-				} else if force_add {
-					add.push(xml::writer::XmlEvent::EndElement {
-						name: Some(name.borrow()),
-					})
-				}
-				
-				layer -= 1;
-				
-				if layer == force_add_layer {
-					force_add = false;
-					is_use = None;
+			None => (color, None),
+		};
+		
+		let mut style = LayerStyle {
+			color: Some(color).filter(|v| !v.is_empty()).map(|v| v.to_owned()),
+			gradient,
+			blend: aetherment::modman::composite::tex::Blend::Normal,
+			opacity: None,
+			mask: None,
+		};
+		
+		let mut blend = None;
+		let mut mask = false;
+		let mut value = None;
+		for part in parts {
+			if let Some(mode) = blend_mode(part) {
+				if let Some(previous) = blend.replace(part) {
+					return Err(format!("Layer label has more than one blend mode ({previous} and {part})"));
 				}
+				style.blend = mode;
+				continue;
 			}
 			
-			xml::reader::XmlEvent::Characters(v) => {
-				if let Some(use_id) = &is_use {
-					let add = use_adds.entry(use_id.clone()).or_insert_with(|| Vec::new());
-					add.push(xml::writer::XmlEvent::Characters(v));
+			if part == "mask" {
+				if mask {
+					return Err("Layer label has more than one mask".to_owned());
 				}
+				mask = true;
+				continue;
 			}
 			
-			_ => {}
+			if value.replace(Factor::parse(part)?).is_some() {
+				return Err(format!("Layer label has more than one {}", if mask {"cull point"} else {"opacity"}));
+			}
+		}
+		
+		if style.gradient.is_some() && blend.is_some() {
+			return Err("Gradient layers can't use a blend mode".to_owned());
+		}
+		
+		if mask {
+			if style.color.is_some() || style.gradient.is_some() || blend.is_some() {
+				return Err("Mask layers can't have a color or blend mode".to_owned());
+			}
+			style.mask = Some(value.unwrap_or(Factor::Static(0.5)));
+		} else {
+			style.opacity = value;
 		}
+		
+		Ok(style)
 	}
 	
-	let mut bad_branch = false;
-	let mut bad_branch_layer = 0;
-	let mut use_is_solved = false;
-	for e in xml.iter() {
-		match e {
-			xml::reader::XmlEvent::StartElement{name, attributes, namespace} => {
-				let label = attributes.iter().find(|&v| v.name.local_name == "label");
-				if !bad_branch && ((layer == 1 && name.local_name.as_str() != "g") || label.map_or(false, |v| v.value.starts_with("_") || v.value.starts_with("+"))) {
-					bad_branch = true;
-					bad_branch_layer = layer;
-				}
-				
-				if !bad_branch {
-					match (layer, name.local_name.as_str()) {
-						(_, "use") => {
-							let href = &attributes.iter().find(|&v| v.name.local_name == "href").unwrap().value[1..];
-							if let Some(add) = use_adds.get(href) {
-								let layers = svgs.get_mut(&path).unwrap().get_mut(&option).unwrap();
-								let len = layers.len();
-								let layer = &mut layers[len - 1].1;
-								
-								layer.write(xml::writer::XmlEvent::StartElement {
-									name: "g".into(),
-									namespace: namespace.borrow(),
-									attributes: attributes.iter().filter(|v| v.name.local_name != "href").map(|v| v.borrow()).collect()
-								})?;
-								
-								for e in add {
-									layer.write(e.clone())?;
-								}
-								
-								layer.write(xml::writer::XmlEvent::EndElement {
-									name: Some("g".into()),
-								})?;
-								
-								use_is_solved = true;
-							} else {
-								let layers = svgs.get_mut(&path).unwrap().get_mut(&option).unwrap();
-								let len = layers.len();
-								layers[len - 1].1.write(xml::writer::XmlEvent::StartElement {
-									name: name.borrow(),
-									namespace: namespace.borrow(),
-									attributes: attributes.iter().map(|v| v.borrow()).collect(),
-								})?;
-								
-								use_is_solved = false;
-							}
-						}
-						
-						(1, "g") => {
-							path = label.ok_or("Svg does not contain path label")?.value.trim().to_ascii_lowercase().to_owned();
-							if path.contains("./") || path.contains(".\\") {
-								panic!("{path} is invalid");
-							}
-							
-							g1 = attributes.iter().filter(|v| v.name.local_name != "style").collect();
-						}
-						
-						(2, "g") => {
-							option = label.ok_or("Svg does not contain option label")?.value.trim().to_owned();
-							g2 = attributes.iter().filter(|v| v.name.local_name != "style").collect();
-						}
-						
-						(3, "g") => {
-							color_option = label.ok_or("Svg does not contain color option label")?.value.trim().to_owned();
-							
-							let layers = svgs.entry(path.clone()).or_insert_with(|| HashMap::new())
-								.entry(option.clone()).or_insert_with(|| Vec::new());
-							
-							let len = layers.len();
-							if len == 0 || layers[len - 1].0 != color_option {
-								let mut writer = xml::EventWriter::new(Cursor::new(Vec::new()));
-								writer.write(xml::writer::XmlEvent::StartElement {
-									name: "svg".into(),
-									namespace: namespace.borrow(),
-									attributes: root_attributes.iter().map(|v| v.borrow()).collect(),
-								})?;
-								
-								for event in &add {
-									writer.write(event.clone())?;
-								}
-								
-								writer.write(xml::writer::XmlEvent::StartElement {
-									name: "g".into(),
-									namespace: namespace.borrow(),
-									attributes: g1.iter().map(|v| v.borrow()).collect(),
-								})?;
-								
-								writer.write(xml::writer::XmlEvent::StartElement {
-									name: "g".into(),
-									namespace: namespace.borrow(),
-									attributes: g2.iter().map(|v| v.borrow()).collect(),
-								})?;
-								
-								writer.write(xml::writer::XmlEvent::StartElement {
-									name: "g".into(),
-									namespace: namespace.borrow(),
-									attributes: attributes.iter().filter(|v| v.name.local_name != "style").map(|v| v.borrow()).collect(),
-								})?;
-								
-								layers.push((color_option.clone(), writer));
-							}
-						}
-						
-						(4.., _) => {
-							let layers = svgs.get_mut(&path).unwrap().get_mut(&option).unwrap();
-							let len = layers.len();
-							layers[len - 1].1.write(xml::writer::XmlEvent::StartElement {
-								name: name.borrow(),
-								namespace: namespace.borrow(),
-								attributes: patch_attributes(name, attributes).iter().map(|v| v.borrow()).collect(),
-							})?;
-						}
-						
-						_ => {}
-					}
+	fn is_plain(&self) -> bool {
+		self.color.is_none() && self.opacity.is_none() && self.mask.is_none() && matches!(self.blend, aetherment::modman::composite::tex::Blend::Normal)
+	}
+	
+	// the options it uses, for checking if they exist
+	fn options(&self) -> impl Iterator<Item = &str> {
+		self.color.as_deref().into_iter()
+			.chain(self.gradient.as_ref().map(|(v, _)| v.as_str()))
+			.chain(self.opacity.as_ref().and_then(Factor::option))
+			.chain(self.mask.as_ref().and_then(Factor::option))
+	}
+}
+
+// the css mix-blend-mode names inkscape also uses, along with add and subtract
+fn blend_mode(name: &str) -> Option<aetherment::modman::composite::tex::Blend> {
+	use aetherment::modman::composite::tex::Blend;
+	Some(match name {
+		"normal" => Blend::Normal,
+		"multiply" => Blend::Multiply,
+		"screen" => Blend::Screen,
+		"overlay" => Blend::Overlay,
+		"hard-light" => Blend::HardLight,
+		"soft-light" => Blend::SoftLight,
+		"add" => Blend::Add,
+		"subtract" => Blend::Subtract,
+		"difference" => Blend::Difference,
+		"darken" => Blend::Darken,
+		"lighten" => Blend::Lighten,
+		_ => return None,
+	})
+}
+
+fn split_svgs(data: &str, file: &Path, lib: &svg::Library) -> Result<Vec<SvgResult>, Error> {
+	let root = svg::Element::parse(data, file)?;
+	let mut ids = lib.ids();
+	ids.extend(root.ids());
+	let splitter = Splitter{file, ids, lib};
+	let mut ancestors = vec![Ancestor::new(&root.attributes)];
+	let mut layers = Vec::<SplitLayer>::new();
+	for g1 in root.elements() {
+		// anything besides the path layers is defs, which every layer gets, or inkscape metadata
+		if !g1.is("g") || splitter.skip(g1, &mut ancestors)? {continue}
+		ancestors.push(Ancestor::new(&g1.attributes));
+		let location = Location{file, position: g1.position, ancestors: &ancestors};
+		let path = g1.label().ok_or_else(|| location.error("Svg does not contain path label"))?.trim().to_ascii_lowercase();
+		if path.contains("./") || path.contains(".\\") {
+			return Err(location.error("Path label is invalid"));
+		}
+		
+		for g2 in g1.elements() {
+			if splitter.skip(g2, &mut ancestors)? {continue}
+			ancestors.push(Ancestor::new(&g2.attributes));
+			let location = Location{file, position: g2.position, ancestors: &ancestors};
+			// used elements have nowhere to go, anything else is reference images and such that only matter in inkscape
+			if !g2.is("g") {
+				if g2.is("use") {
+					return Err(location.error("Element is not inside of a color option layer"));
 				}
 				
-				layer += 1;
+				ancestors.pop();
+				continue;
 			}
 			
-			xml::reader::XmlEvent::EndElement{name} => {
-				layer -= 1;
-				
-				if !bad_branch {
-					match (layer, name.local_name.as_str()) {
-						(_, "use") => {
-							if !use_is_solved {
-								let layers = svgs.get_mut(&path).unwrap().get_mut(&option).unwrap();
-								let len = layers.len();
-								let layer = &mut layers[len - 1].1;
-								layer.write(xml::writer::XmlEvent::EndElement {
-									name: Some(name.borrow()),
-								})?;
-							}
-						}
-						
-						(1, "g") => {
-							path.clear();
-						}
-						
-						(2, "g") => {
-							option.clear();
-						}
-						
-						(3, "g") => {
-							color_option.clear();
-						}
-						
-						(4.., _) => {
-							let layers = svgs.get_mut(&path).unwrap().get_mut(&option).unwrap();
-							let len = layers.len();
-							let layer = &mut layers[len - 1].1;
-							layer.write(xml::writer::XmlEvent::EndElement {
-								name: Some(name.borrow()),
-							})?;
-						}
-						
-						_ => {}
+			let option = g2.label().ok_or_else(|| location.error("Svg does not contain option label"))?.trim().to_owned();
+			if let Some(o) = option.split(";").find(|v| !v.trim().is_empty() && !v.contains(":")) {
+				return Err(location.error(format!("Option {o} is not in the Option:Sub format")));
+			}
+			
+			for g3 in g2.elements() {
+				if splitter.skip(g3, &mut ancestors)? {continue}
+				ancestors.push(Ancestor::new(&g3.attributes));
+				let location = Location{file, position: g3.position, ancestors: &ancestors};
+				// used elements have nowhere to go, anything else is reference images and such that only matter in inkscape
+				if !g3.is("g") {
+					if g3.is("use") {
+						return Err(location.error("Element is not inside of a color option layer"));
 					}
+					
+					ancestors.pop();
+					continue;
 				}
 				
-				if layer == bad_branch_layer {
-					bad_branch = false;
+				let color_option = g3.label().ok_or_else(|| location.error("Svg does not contain color option label"))?.trim().to_owned();
+				let style = LayerStyle::parse(&color_option).map_err(|e| location.error(e))?;
+				let merges = layers.iter().rev().find(|v| v.path == path && v.option == option).is_some_and(|v| v.color_option == color_option);
+				if style.mask.is_some() && !merges && layers.iter().any(|v| v.path == path && v.option == option && v.style.mask.is_some()) {
+					return Err(location.error("Texture already has a mask layer"));
 				}
-			}
-			
-			xml::reader::XmlEvent::Characters(v) => {
-				if layer >= 4 {
-					if let Some(a) = svgs.get_mut(&path) {
-						if let Some(layers) = a.get_mut(&option) {
-							let len = layers.len();
-							layers[len - 1].1.write(xml::writer::XmlEvent::Characters(v))?;
-						}
-					}
+				
+				let content = splitter.resolve_children(g3, &mut ancestors, &mut Vec::new())?;
+				// the layer groups keep their transforms and such, but their style is only there to show or hide them in inkscape
+				let wrap = |g: &svg::Element, children| svg::Node::Element(g.with(g.attributes.iter().filter(|v| v.name.local_name != "style").cloned().collect(), children));
+				let svg::Node::Element(group) = wrap(g1, vec![wrap(g2, vec![wrap(g3, content)])]) else {unreachable!()};
+				
+				// following color option groups with the same label make up a single layer
+				match layers.iter_mut().rev().find(|v| v.path == path && v.option == option) {
+					Some(layer) if layer.color_option == color_option => layer.groups.push(group),
+					_ => layers.push(SplitLayer{path: path.clone(), option: option.clone(), color_option, style, groups: vec![group]}),
 				}
+				
+				ancestors.pop();
 			}
 			
-			_ => {}
+			ancestors.pop();
 		}
+		
+		ancestors.pop();
 	}
 	
-	Ok(svgs.into_iter().flat_map(|(path, options)| {
-		options.into_iter().flat_map(move |(option_unsplit, layers)| {
-			let layers = layers.into_iter().map(|(color_option, mut svg)| {
-				svg.write(xml::writer::XmlEvent::EndElement{name: Some("g".into())}).unwrap();
-				svg.write(xml::writer::XmlEvent::EndElement{name: Some("g".into())}).unwrap();
-				svg.write(xml::writer::XmlEvent::EndElement{name: Some("g".into())}).unwrap();
-				svg.write(xml::writer::XmlEvent::EndElement{name: Some("svg".into())}).unwrap();
-				
-				(if color_option.trim().len() == 0 {
-					None
-				} else {
-					Some(color_option)
-				}, String::from_utf8(svg.into_inner().into_inner()).unwrap())
-			}).collect::<Vec<(Option<String>, String)>>();
-			
-			option_unsplit.split(";").into_iter().map(|option|
-				SvgResult {
-					path: path.clone(),
-					option: if option.trim().len() == 0 {
-						None
-					} else {
-						let mut s = option.split(":");
-						println!("{}", path);
-						Some((s.next().unwrap().to_owned(), s.next().unwrap().to_owned()))
-					},
-					layers: layers.clone(),
+	let defs = root.elements().filter(|v| v.is("defs")).collect::<Vec<_>>();
+	let mut options = Vec::<(&str, &str, Vec<(LayerStyle, String)>)>::new();
+	for layer in &layers {
+		let svg = splitter.layer_svg(&root, &defs, &layer.groups)?;
+		match options.iter_mut().find(|(path, option, _)| *path == layer.path && *option == layer.option) {
+			Some((_, _, layers)) => layers.push((layer.style.clone(), svg)),
+			None => options.push((&layer.path, &layer.option, vec![(layer.style.clone(), svg)])),
+		}
+	}
+	
+	let mut results = Vec::new();
+	for (path, option_unsplit, layers) in options {
+		for option in option_unsplit.split(";") {
+			results.push(SvgResult {
+				path: path.to_owned(),
+				// the format was already checked while splitting
+				option: option.split_once(":").map(|(o1, o2)| (o1.to_owned(), o2.to_owned())),
+				layers: layers.clone(),
+			});
+		}
+	}
+	
+	Ok(results)
+}
+
+// the color option groups that make up a layer of a split svg, each wrapped in copies of its path and option groups
+struct SplitLayer {
+	path: String,
+	option: String,
+	color_option: String,
+	style: LayerStyle,
+	groups: Vec<svg::Element>,
+}
+
+struct Splitter<'a> {
+	file: &'a Path,
+	// of the svg and the library
+	ids: HashMap<&'a str, &'a svg::Element>,
+	lib: &'a svg::Library,
+}
+
+impl<'a> Splitter<'a> {
+	// whether the element is left out, reusable groups still need an id to be used by
+	fn skip(&self, element: &svg::Element, ancestors: &mut Vec<Ancestor>) -> Result<bool, Error> {
+		if element.is_reusable() && element.id().is_none() {
+			ancestors.push(Ancestor::new(&element.attributes));
+			return Err(Location{file: self.file, position: element.position, ancestors}.error("Reusable group has no id"));
+		}
+		
+		Ok(element.is_skipped())
+	}
+	
+	// the children of the element that get rendered, with the elements <use> refers to in place of it
+	fn resolve_children(&self, element: &'a svg::Element, ancestors: &mut Vec<Ancestor>, using: &mut Vec<&'a str>) -> Result<Vec<svg::Node>, Error> {
+		let mut children = Vec::new();
+		for child in &element.children {
+			match child {
+				svg::Node::Text(v) => children.push(svg::Node::Text(v.clone())),
+				svg::Node::Element(v) => {
+					if self.skip(v, ancestors)? {continue}
+					ancestors.push(Ancestor::new(&v.attributes));
+					let resolved = self.resolve(v, ancestors, using);
+					ancestors.pop();
+					children.push(svg::Node::Element(resolved?));
 				}
-			).collect::<Vec<SvgResult>>()
-		})
-	}).collect())
+			}
+		}
+		
+		Ok(children)
+	}
+	
+	fn resolve(&self, element: &'a svg::Element, ancestors: &mut Vec<Ancestor>, using: &mut Vec<&'a str>) -> Result<svg::Element, Error> {
+		if !element.is("use") {
+			return Ok(element.with(patch_attributes(&element.name, &element.attributes), self.resolve_children(element, ancestors, using)?));
+		}
+		
+		let location = Location{file: self.file, position: element.position, ancestors};
+		let href = element.attribute("href").ok_or_else(|| location.error("Use element has no href"))?;
+		if using.contains(&href) {
+			return Err(location.error(format!("Use element refers to {href}, which uses itself")));
+		}
+		
+		// parts of the library come with their defs, as the svg using them doesn't have those
+		let (target, part) = if let Some(reference) = href.strip_prefix("lib:") {
+			let (part, target) = self.lib.find(reference).ok_or_else(|| location.error(format!("Use element refers to {href}, which is not in the library")))?;
+			(target, Some(part))
+		} else {
+			let id = href.strip_prefix('#').ok_or_else(|| location.error(format!("Use element refers to {href}, which is neither in this svg nor the library")))?;
+			(*self.ids.get(id).ok_or_else(|| location.error(format!("Use element refers to {href}, which does not exist")))?, None)
+		};
+		
+		using.push(href);
+		let resolved = self.resolve_target(target, part, ancestors, using);
+		using.pop();
+		
+		// every use is a copy of its own, so the ids in it can't be the ones of the original or other copies
+		let mut resolved = element.with(Vec::new(), resolved?);
+		resolved.prefix_ids(&format!("use{}_{}_", element.position.row + 1, element.position.column + 1));
+		
+		// x and y move the used element after the transform of the use element
+		let mut transform = element.attribute("transform").unwrap_or("").to_owned();
+		let (x, y) = (element.attribute("x").unwrap_or("0"), element.attribute("y").unwrap_or("0"));
+		if x != "0" || y != "0" {
+			transform = format!("{transform} translate({x},{y})").trim().to_owned();
+		}
+		
+		let mut attributes = element.attributes.iter()
+			.filter(|v| !matches!(v.name.local_name.as_str(), "href" | "x" | "y" | "width" | "height" | "transform"))
+			.cloned()
+			.collect::<Vec<_>>();
+		if !transform.is_empty() {
+			attributes.push(xml::attribute::OwnedAttribute::new(xml::name::OwnedName::local("transform"), transform));
+		}
+		
+		Ok(element.with(attributes, resolved.children).renamed("g"))
+	}
+	
+	// what a <use> gets replaced with
+	fn resolve_target(&self, target: &'a svg::Element, part: Option<&'a svg::Element>, ancestors: &mut Vec<Ancestor>, using: &mut Vec<&'a str>) -> Result<Vec<svg::Node>, Error> {
+		// a whole part of the library, everything in it but what only matters to inkscape
+		if part.is_some_and(|v| std::ptr::eq(v, target)) {
+			let mut children = self.resolve_children(target, ancestors, using)?;
+			children.retain(|v| !matches!(v, svg::Node::Element(v) if v.is("namedview") || v.is("metadata")));
+			return Ok(children);
+		}
+		
+		// reusable groups are hidden in inkscape
+		let mut resolved = self.resolve(target, ancestors, using)?;
+		resolved.attributes.retain(|v| !(target.is_reusable() && v.name.local_name == "style"));
+		
+		let mut children = Vec::new();
+		for defs in part.iter().flat_map(|v| v.elements()).filter(|v| v.is("defs")) {
+			children.push(svg::Node::Element(self.resolve(defs, ancestors, using)?));
+		}
+		children.push(svg::Node::Element(resolved));
+		
+		Ok(children)
+	}
+	
+	// a standalone svg of the groups, with the defs of the source svg and anything else the groups refer to that isn't in them
+	fn layer_svg(&self, root: &'a svg::Element, defs: &[&svg::Element], groups: &[svg::Element]) -> Result<String, Error> {
+		let mut defined = svg::defined(defs.iter().copied().chain(groups)).into_iter().map(|v| v.to_owned()).collect::<HashSet<_>>();
+		let mut references = defs.iter().copied().chain(groups).flat_map(|v| v.references()).map(|v| v.to_owned()).collect::<Vec<_>>();
+		let mut extra = Vec::new();
+		let mut ancestors = vec![Ancestor::new(&root.attributes)];
+		while let Some(id) = references.pop() {
+			if defined.contains(&id) {continue}
+			// broken references are left for resvg to ignore, like inkscape does
+			let Some(target) = self.ids.get(id.as_str()) else {continue};
+			let resolved = self.resolve(target, &mut ancestors, &mut Vec::new())?;
+			defined.extend(svg::defined([&resolved]).into_iter().map(|v| v.to_owned()));
+			references.extend(resolved.references().into_iter().map(|v| v.to_owned()));
+			extra.push(svg::Node::Element(resolved));
+		}
+		
+		let mut children = defs.iter().map(|v| svg::Node::Element((*v).clone())).collect::<Vec<_>>();
+		if !extra.is_empty() {
+			children.push(svg::Node::Element(root.with(Vec::new(), extra).renamed("defs")));
+		}
+		children.extend(groups.iter().map(|v| svg::Node::Element(v.clone())));
+		
+		let mut writer = xml::EventWriter::new(Cursor::new(Vec::new()));
+		root.with(root.attributes.clone(), children).write(&mut writer)?;
+		Ok(String::from_utf8(writer.into_inner().into_inner())?)
+	}
+}
+
+struct Ancestor {
+	id: Option<String>,
+	label: Option<String>,
+}
+
+impl Ancestor {
+	fn new(attributes: &[xml::attribute::OwnedAttribute]) -> Self {
+		Self {
+			id: attributes.iter().find(|v| v.name.local_name == "id").map(|v| v.value.clone()),
+			label: attributes.iter().find(|v| v.name.local_name == "label").map(|v| v.value.clone()),
+		}
+	}
+}
+
+// where an element is in the source svg, so that errors about the layer structure can point to it
+struct Location<'a> {
+	file: &'a Path,
+	position: xml::common::TextPosition,
+	// every element from the root to the current one
+	ancestors: &'a [Ancestor],
+}
+
+impl<'a> Location<'a> {
+	fn at(&self) -> String {
+		format!("{}:{}:{}", self.file.to_string_lossy(), self.position.row + 1, self.position.column + 1)
+	}
+	
+	// adds the position, id and layer chain as context
+	fn locate(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+		diagnostic = diagnostic.context("at", self.at());
+		
+		if let Some(id) = self.ancestors.last().and_then(|v| v.id.as_ref()) {
+			diagnostic = diagnostic.context("id", id);
+		}
+		
+		let labels = layer_chain(self.ancestors.iter().filter_map(|v| v.label.as_deref()));
+		if !labels.is_empty() {
+			diagnostic = diagnostic.context("layer", labels);
+		}
+		
+		diagnostic
+	}
+	
+	fn error(&self, message: impl Into<String>) -> Error {
+		Box::new(self.locate(Diagnostic::error(message)))
+	}
 }
 
-fn render_svg(svg: SvgResult, target_root: &Path, font: &resvg::usvg::fontdb::Database/*, color_paths: &mut HashMap<String, HashSet<String>>*/) -> Result<(), Error> {
+// the labels of a layer and its parents, ui/uld/ButtonA_hr1.tex > Shape Style:Rounded > Secondary Color
+fn layer_chain<'a>(labels: impl IntoIterator<Item = &'a str>) -> String {
+	labels.into_iter().map(|v| v.trim()).filter(|v| !v.is_empty()).collect::<Vec<_>>().join(" > ")
+}
+
+fn render_svg(svg: SvgResult, scale: f32, target_root: &Path, font: &resvg::usvg::fontdb::Database, cache: &cache::Cache, tex: &compression::Rules, artifacts: &artifacts::Artifacts/*, color_paths: &mut HashMap<String, HashSet<String>>*/) -> Result<Vec<(String, cache::Entry)>, Error> {
 	let local_dir = if let Some((o1, o2)) = &svg.option {
 		format!("{}/{o1}/{o2}", svg.path.clone())
 	} else {
@@ -723,30 +1025,65 @@ fn render_svg(svg: SvgResult, target_root: &Path, font: &resvg::usvg::fontdb::Da
 	let dir = target_root.join(&local_dir);
 	_ = std::fs::create_dir_all(&dir);
 	
-	if svg.layers.len() > 1 || svg.layers[0].0 != None { // composite info
+	let mut entries = Vec::new();
+	let composite = svg.is_composite();
+	let settings = if composite {tex.layer(&svg.path)} else {tex.file(&svg.path)};
+	let supersample = tex.supersample(&svg.path);
+	if composite { // composite info
 		use aetherment::modman::{Path, composite::tex::*};
 		
+		// mask layers aren't drawn, every other layer is culled by them
+		let masks = svg.layers.iter().enumerate().filter_map(|(i, (style, _))| Some(Modifier::AlphaMask {
+			path: Path::Mod(format!("{local_dir}/{i}.tex")),
+			cull_point: match style.mask.as_ref()? {
+				Factor::Static(v) => OptionOrStatic::Static(*v),
+				Factor::Option(v) => OptionOrStatic::Option(MaskOption(v.to_owned())),
+			},
+		})).collect::<Vec<_>>();
+		
 		let mut layers = Vec::new();
-		for (i, (color_option, _layer)) in svg.layers.iter().enumerate().rev() {
+		for (i, (style, _layer)) in svg.layers.iter().enumerate().rev() {
+			if style.mask.is_some() {continue}
+			
+			// opacity is a color that only has alpha, colors multiply with each other
+			let opacity = match &style.opacity {
+				Some(Factor::Static(v)) => Some(Modifier::Color{value: OptionOrStatic::Static([1.0, 1.0, 1.0, *v])}),
+				Some(Factor::Option(v)) => Some(Modifier::Color{value: OptionOrStatic::Option(ColorOption(v.to_owned()))}),
+				None => None,
+			};
+			
+			let modifiers = |color: Option<Modifier>| color.into_iter().chain(opacity.clone()).chain(masks.iter().cloned()).collect::<Vec<_>>();
+			let color = |option: &str| Modifier::Color{value: OptionOrStatic::Option(ColorOption(option.to_owned()))};
+			
+			// composites have no gradients, so the second color is drawn over the first with the position as its opacity
+			// which is exact where the layer is opaque
+			if let Some((to, position)) = &style.gradient {
+				let mut gradient = modifiers(Some(color(to)));
+				gradient.insert(1, Modifier::Color{value: OptionOrStatic::Static([1.0, 1.0, 1.0, *position])});
+				layers.push(Layer {
+					name: format!("Layer{i}Gradient"),
+					path: Path::Mod(format!("{local_dir}/{i}.tex")),
+					blend: style.blend,
+					modifiers: gradient,
+				});
+			}
+			
 			layers.push(Layer {
 				name: format!("Layer{i}"),
 				path: Path::Mod(format!("{local_dir}/{i}.tex")),
-				blend: Blend::Normal,
-				modifiers: if let Some(color_option) = color_option {
-					// color_paths.entry(color_option.to_owned()).or_insert_with(|| HashSet::new()).insert(svg.path.clone());
-					
-					vec![
-						Modifier::Color {
-							value: OptionOrStatic::Option(ColorOption(color_option.to_owned()))
-						}
-					]
-				} else {
-					Vec::new()
-				}
+				blend: style.blend,
+				modifiers: modifiers(style.color.as_deref().map(color)),
 			});
 		}
 		
-		std::fs::write(dir.join("comp.tex.comp"), serde_json::to_string(&Tex{layers: layers})?)?;
+		let comp = serde_json::to_string(&Tex{layers: layers})?;
+		let key = format!("{local_dir}/comp.tex.comp");
+		let hash = cache::hash(&[comp.as_bytes()]);
+		if !cache.is_fresh(target_root, &key, &hash) {
+			std::fs::write(dir.join("comp.tex.comp"), comp)?;
+		}
+		
+		entries.push((key.clone(), cache::Entry{hash, files: vec![key]}));
 	}
 	
 	let opt = resvg::usvg::Options {
@@ -755,33 +1092,74 @@ fn render_svg(svg: SvgResult, target_root: &Path, font: &resvg::usvg::fontdb::Da
 		..Default::default()
 	};
 	
-	for (i, (_color_option, layer)) in svg.layers.into_iter().enumerate() {
-		let tree = resvg::usvg::Tree::from_str(&layer, &opt, &font)?;
-		let size = tree.size().to_int_size();
-		let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("Failed creating pixmap with specified size")?;
-		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+	for (i, (style, layer)) in svg.layers.into_iter().enumerate() {
+		let key = format!("{local_dir}/{i}");
+		let path = dir.join(format!("{i}.tex"));
+		let options = format!("{settings:?}");
+		let size = format!("{scale}x{supersample}");
+		let mut parts = vec![layer.as_bytes(), options.as_bytes(), size.as_bytes(), cache::RENDER_VERSION.as_bytes()];
+		if style.mask.is_some() {
+			parts.push(b"mask");
+		}
+		let hash = cache::hash(&parts);
+		let files = vec![format!("{key}.tex")];
+		if cache.is_fresh(target_root, &key, &hash) && artifacts.exist(&path) {
+			entries.push((key, cache::Entry{hash, files}));
+			continue;
+		}
 		
-		{ // svg (debug purposes)
-			std::fs::write(dir.join(format!("{i}.svg")), &layer)?
+		let tree = resvg::usvg::Tree::from_str(&layer, &opt, &font)
+			.map_err(|e| Diagnostic::error(format!("Failed parsing layer: {e}")).context("layer", layer_chain([svg.path.as_str(), &svg.option.as_ref().map_or(String::new(), |(o1, o2)| format!("{o1}:{o2}")), style.color.as_deref().unwrap_or("")])))?;
+		let (width, height, mut data) = rasterize(&tree, scale, supersample)?;
+		if style.mask.is_some() {
+			data = luminance_mask(&data);
 		}
 		
+		artifacts.svg(&path, &layer)?;
+		
 		// { // png
 		// 	pixmap.save_png(dir.join(format!("{i}.png")))?
 		// }
 		
 		{ // tex
-			for pixel in pixmap.pixels_mut() {
-				let c = pixel.demultiply();
-				// fuck you tiny_skia for making PremultipliedColorU8::from_rgba_unchecked private,
-				// could've make a new vec but dont want to alloc memory
-				*pixel = unsafe{std::mem::transmute::<_, _>([c.red(), c.green(), c.blue(), c.alpha()])};
-			}
-			
-			save_tex(size.width() as u16, size.height() as u16, pixmap.data(), &dir.join(format!("{i}.tex")))?;
+			save_tex(width as u16, height as u16, &data, &path, settings, artifacts)?;
 		}
+		
+		entries.push((key, cache::Entry{hash, files}));
 	}
 	
-	Ok(())
+	Ok(entries)
+}
+
+// the mask texture alpha masks read, luminance times alpha like svg masks
+fn luminance_mask(rgba: &[u8]) -> Vec<u8> {
+	rgba.chunks_exact(4).flat_map(|v| {
+		let luminance = v[0] as f32 * 0.299 + v[1] as f32 * 0.587 + v[2] as f32 * 0.114;
+		let value = (luminance * v[3] as f32 / 255.0).round() as u8;
+		[value, value, value, 255]
+	}).collect()
+}
+
+// renders at scale times the size of the svg to straight (not premultiplied) rgba8, supersampled if more than 1
+fn rasterize(tree: &resvg::usvg::Tree, scale: f32, supersample: u32) -> Result<(u32, u32, Vec<u8>), Error> {
+	let size = tree.size().to_int_size();
+	let size = resvg::tiny_skia::IntSize::from_wh((size.width() as f32 * scale).ceil() as u32, (size.height() as f32 * scale).ceil() as u32).ok_or("Scaled size is empty")?;
+	let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width() * supersample, size.height() * supersample).ok_or("Failed creating pixmap with specified size")?;
+	let render_scale = scale * supersample as f32;
+	resvg::render(tree, resvg::tiny_skia::Transform::from_scale(render_scale, render_scale), &mut pixmap.as_mut());
+	if supersample > 1 {
+		// averaging premultiplied pixels keeps them valid premultiplied pixels
+		pixmap = resvg::tiny_skia::Pixmap::from_vec(mips::shrink(pixmap.width(), pixmap.height(), pixmap.data(), supersample), size).ok_or("Failed creating pixmap with specified size")?;
+	}
+	
+	for pixel in pixmap.pixels_mut() {
+		let c = pixel.demultiply();
+		// fuck you tiny_skia for making PremultipliedColorU8::from_rgba_unchecked private,
+		// could've make a new vec but dont want to alloc memory
+		*pixel = unsafe{std::mem::transmute::<[u8; 4], resvg::tiny_skia::PremultipliedColorU8>([c.red(), c.green(), c.blue(), c.alpha()])};
+	}
+	
+	Ok((size.width(), size.height(), pixmap.take()))
 }
 
 // svg is kinda a mess, here we patch stuff so that we may have incorrect behaviour but it atleast shows up like it does in inkscape
@@ -798,38 +1176,48 @@ fn patch_attributes<'a>(name: &xml::name::OwnedName, att: &Vec<xml::attribute::O
 	att
 }
 
-pub fn save_tex(width: u16, height: u16, data: &[u8], path: &Path) -> Result<(), Error> {
-	let mut f = BufWriter::new(File::create(path)?);
-	
-	// header
-	f.write(&0x00800000u32.to_le_bytes())?;
-	f.write(&0x1450u32.to_le_bytes())?;
-	f.write(&width.to_le_bytes())?;
-	f.write(&height.to_le_bytes())?;
-	f.write(&0u16.to_le_bytes())?;
-	f.write(&1u16.to_le_bytes())?;
-	for v in [0u32, 1, 2] {
-		f.write(&v.to_le_bytes())?;
-	}
-	for v in [80u32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] {
-		f.write(&v.to_le_bytes())?;
-	}
-	
-	// body
-	for v in data.chunks_exact(4) {
-		f.write(&v[2].to_le_bytes())?;
-		f.write(&v[1].to_le_bytes())?;
-		f.write(&v[0].to_le_bytes())?;
-		f.write(&v[3].to_le_bytes())?;
-	}
-	
-	// png for debugging
-	let img = image::RgbaImage::from_vec(width as _, height as _, data.to_vec()).unwrap();
-	img.save(path.with_extension("png"))?;
-	// match image::RgbaImage::from_vec(width as _, height as _, data.to_vec()) {
-	// 	Some(img) => img.save(path.with_extension("png"))?,
-	// 	None => println!("failed png {path:?}"),
-	// }
+pub fn save_tex(width: u16, height: u16, data: &[u8], path: &Path, settings: compression::Settings, artifacts: &artifacts::Artifacts) -> Result<(), Error> {
+	tex::Tex::new(width, height, data, settings).write(&mut BufWriter::new(File::create(path)?))?;
+	artifacts.png(path, width as u32, height as u32, data)?;
 	
 	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn failed_svg_keeps_outputs() {
+		let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+		let target_root = std::env::temp_dir().join(format!("preprocessor-render-{}", std::process::id()));
+		_ = std::fs::remove_dir_all(&target_root);
+		let svg_root = target_root.join("svg");
+		std::fs::create_dir_all(&svg_root).unwrap();
+		std::fs::copy(root.join("fixtures").join("blend.svg"), svg_root.join("blend.svg")).unwrap();
+		
+		let lib = svg::Library::load(&root.join("fixtures").join("lib")).unwrap();
+		let files_root = target_root.join("files");
+		let artifacts = artifacts::Artifacts{emit: Vec::new(), files_root: files_root.clone(), debug_root: target_root.join("debug")};
+		let render = |diag: &Diagnostics| render(&svg_root, &lib, &target_root, true, &compression::Rules::default(), &artifacts, diag).unwrap();
+		
+		let diag = Diagnostics::default();
+		render(&diag);
+		assert_eq!(diag.errors(), 0);
+		let outputs = cache::Cache::load(&target_root).entries.into_values().flat_map(|v| v.files).collect::<Vec<_>>();
+		assert!(!outputs.is_empty());
+		
+		// breaking the svg must not prune what it rendered before, neither now nor on the next run
+		std::fs::write(svg_root.join("blend.svg"), "<svg").unwrap();
+		for _ in 0..2 {
+			let diag = Diagnostics::default();
+			render(&diag);
+			assert_eq!(diag.errors(), 1);
+			for file in &outputs {
+				assert!(files_root.join(file).exists(), "{file} was pruned");
+			}
+		}
+		
+		_ = std::fs::remove_dir_all(&target_root);
+	}
 }
\ No newline at end of file
@@ -0,0 +1,45 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+// game path > mod path, grouped by the option and sub option they belong to (None being the base files)
+pub type Files = HashMap<Option<(String, String)>, HashMap<String, String>>;
+
+// the order in which stages get combined, later ones overwrite paths of earlier ones
+pub const STAGES: [&str; 4] = ["render", "uld", "icons", "merge"];
+
+fn manifest_path(target_root: &Path, stage: &str) -> PathBuf {
+	target_root.join(".preprocessor").join(format!("{stage}.json"))
+}
+
+// each stage stores the files it created so that meta.json can be created without running every stage again
+pub fn save(target_root: &Path, stage: &str, files: &Files) -> Result<(), crate::Error> {
+	let path = manifest_path(target_root, stage);
+	_ = std::fs::create_dir_all(path.parent().unwrap());
+	// json doesn't allow non string keys, so store it as a list instead
+	std::fs::write(path, serde_json::to_vec(&files.iter().collect::<Vec<_>>())?)?;
+	
+	Ok(())
+}
+
+pub fn load(target_root: &Path) -> Result<Files, crate::Error> {
+	let mut files = Files::new();
+	for stage in STAGES {
+		let path = manifest_path(target_root, stage);
+		if !path.exists() {
+			println!("No files exist for stage {stage}, run it first if it is needed");
+			continue;
+		}
+		
+		merge(&mut files, serde_json::from_slice::<Vec<(Option<(String, String)>, HashMap<String, String>)>>(&std::fs::read(path)?)?);
+	}
+	
+	Ok(files)
+}
+
+pub fn merge(files: &mut Files, other: impl IntoIterator<Item = (Option<(String, String)>, HashMap<String, String>)>) {
+	for (k, v) in other {
+		let paths = files.entry(k).or_default();
+		for (g, r) in v {
+			paths.insert(g, r);
+		}
+	}
+}
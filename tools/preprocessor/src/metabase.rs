@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 use aetherment::modman::{OptionOrStatic, OptionValue};
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +17,30 @@ pub struct MetaBase {
 	pub presets: Vec<HashMap<String, HashMap<String, ValueBase>>>,
 	pub colors: HashMap<u32, OptionOrStaticBase<[f32; 3]>>,
 	pub style: StyleBase,
+	// the table of the jobs option, which load replaces with the options it stands for
+	#[serde(skip)] pub jobs: Option<OptionValueJobsBase>,
+}
+
+impl MetaBase {
+	pub fn load(path: &Path) -> Result<Self, crate::Error> {
+		let mut meta = serde_yaml::from_slice::<MetaBase>(&std::fs::read(path)?)?;
+		let mut options = Vec::new();
+		for option in std::mem::take(&mut meta.options) {
+			let name = option.keys().next().unwrap();
+			match option.values().next().unwrap() {
+				OptionBase::Jobs(jobs) => {
+					if meta.jobs.is_some() {return Err(format!("Option {name}: there can only be one jobs option").into())}
+					options.extend(jobs.options(name).map_err(|e| format!("Option {name}: {e}"))?);
+					meta.jobs = Some(jobs.clone());
+				}
+				
+				_ => options.push(option),
+			}
+		}
+		
+		meta.options = options;
+		Ok(meta)
+	}
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -26,6 +50,7 @@ pub enum OptionBase {
 	Color(OptionValueColorBase),
 	Files(OptionValueFilesBase),
 	Grouped(OptionValueGroupedBase),
+	Jobs(OptionValueJobsBase),
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
@@ -34,6 +59,18 @@ pub struct OptionValueColorBase {
 	pub default: Vec<f32>,
 	pub min: Vec<f32>,
 	pub max: Vec<f32>,
+	// single value colors are grayscale, unless they're the opacity of layers
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")] pub opacity: bool,
+}
+
+impl OptionValueColorBase {
+	// the value as the color it multiplies layers with
+	pub fn color(&self, value: &[f32]) -> Vec<f32> {
+		match value {
+			[v] if self.opacity => vec![1.0, 1.0, 1.0, *v],
+			_ => value.to_vec(),
+		}
+	}
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
@@ -47,6 +84,8 @@ pub struct OptionValueFilesBase {
 pub struct OptionValueFilesSubBase {
 	#[serde(default)] pub description: String,
 	pub inherit: Option<String>,
+	// game path of the texture the previews subcommand shows, the largest one of the option if not set
+	#[serde(default, skip_serializing_if = "Option::is_none")] pub preview: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
@@ -56,6 +95,135 @@ pub struct OptionValueGroupedBase {
 	pub options: Vec<HashMap<String, Vec<HashMap<String, GroupedTypeBase>>>>,
 }
 
+// a color option for every job and a grouped option of them named after this one
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct OptionValueJobsBase {
+	#[serde(default)] pub description: String,
+	pub default: Option<String>,
+	pub roles: Vec<RoleBase>,
+	// in the order of their options
+	pub jobs: Vec<JobBase>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct RoleBase {
+	pub name: String,
+	// the default of the colors of its jobs
+	pub color: Vec<f32>,
+	// its entry in the grouped option, <name> Color if not set
+	pub group: Option<String>,
+	// the entry of the basic grouping it's part of, its group if not set
+	pub basic: Option<String>,
+	// a color option of the role itself, for its role icon
+	pub option: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct JobBase {
+	pub abbreviation: String,
+	// the job a class turns into, classes have the role and color of it
+	pub job: Option<String>,
+	pub role: Option<String>,
+	// place in the job icon family, 062001 being 1
+	pub icon: usize,
+	pub color: Option<String>,
+}
+
+impl RoleBase {
+	pub fn group(&self) -> String {
+		self.group.clone().unwrap_or_else(|| format!("{} Color", self.name))
+	}
+}
+
+impl OptionValueJobsBase {
+	// the role and color option of a job, those of its job for classes
+	pub fn resolve<'a>(&'a self, job: &'a JobBase) -> Result<(&'a RoleBase, &'a str), crate::Error> {
+		let job = match &job.job {
+			Some(name) => self.jobs.iter().find(|v| v.job.is_none() && v.abbreviation == *name).ok_or_else(|| format!("{} turns into {name}, which isn't a job", job.abbreviation))?,
+			None => job,
+		};
+		
+		let role = job.role.as_ref().ok_or_else(|| format!("{} has no role", job.abbreviation))?;
+		let role = self.roles.iter().find(|v| v.name == *role).ok_or_else(|| format!("{} has the role {role}, which doesn't exist", job.abbreviation))?;
+		let color = job.color.as_deref().ok_or_else(|| format!("{} has no color option", job.abbreviation))?;
+		Ok((role, color))
+	}
+	
+	// the color option of the job with the icon
+	pub fn icon_color(&self, icon: usize) -> Option<&str> {
+		let job = self.jobs.iter().find(|v| v.icon == icon)?;
+		self.resolve(job).ok().map(|(_, color)| color)
+	}
+	
+	pub fn role_color(&self, role: &str) -> Option<&str> {
+		self.roles.iter().find(|v| v.name == role)?.option.as_deref()
+	}
+	
+	pub fn options(&self, name: &str) -> Result<Vec<HashMap<String, OptionBase>>, crate::Error> {
+		let mut icons = HashMap::new();
+		let mut colors = Vec::<(&RoleBase, &str)>::new();
+		for job in &self.jobs {
+			if let Some(other) = icons.insert(job.icon, &job.abbreviation) {
+				return Err(format!("{} and {other} have the same icon", job.abbreviation).into());
+			}
+			
+			let (role, color) = self.resolve(job)?;
+			if job.job.is_some() {continue}
+			
+			// the role color goes with those of its jobs, ahead of them
+			for color in role.option.as_deref().into_iter().chain([color]) {
+				if !colors.iter().any(|(_, v)| *v == color) {
+					colors.push((role, color));
+				}
+			}
+		}
+		
+		let mut options = colors.iter().map(|(role, color)| HashMap::from([(color.to_string(), OptionBase::Color(OptionValueColorBase {
+			description: String::new(),
+			default: role.color.clone(),
+			min: vec![0.0; role.color.len()],
+			max: vec![1.0; role.color.len()],
+			opacity: false,
+		}))])).collect::<Vec<_>>();
+		
+		// groups of colors, in the order of the first color of each
+		let grouping = |key: &dyn Fn(&RoleBase) -> String| {
+			let mut groups = Vec::<(String, Vec<String>)>::new();
+			for (role, color) in &colors {
+				let key = key(role);
+				match groups.iter_mut().find(|(v, _)| *v == key) {
+					Some((_, group)) => group.push(color.to_string()),
+					None => groups.push((key, vec![color.to_string()])),
+				}
+			}
+			
+			groups.into_iter().map(|(key, colors)| HashMap::from([(key, GroupedTypeBase::Option(colors))])).collect::<Vec<_>>()
+		};
+		
+		let mut individual = Vec::new();
+		for (i, (role, color)) in colors.iter().enumerate() {
+			if i == 0 || colors[i - 1].0.name != role.name {
+				individual.push(HashMap::from([(role.name.clone(), GroupedTypeBase::Category(0))]));
+			}
+			
+			individual.push(HashMap::from([(color.to_string(), GroupedTypeBase::Option(vec![color.to_string()]))]));
+		}
+		
+		options.push(HashMap::from([(name.to_owned(), OptionBase::Grouped(OptionValueGroupedBase {
+			description: self.description.clone(),
+			default: self.default.clone(),
+			options: vec![
+				HashMap::from([("Merged".to_string(), grouping(&|_| "All".to_string()))]),
+				HashMap::from([("Basic".to_string(), grouping(&|role| role.basic.clone().unwrap_or_else(|| role.group())))]),
+				HashMap::from([("Grouped".to_string(), grouping(&|role| role.group()))]),
+				HashMap::from([("Individual".to_string(), individual)]),
+			],
+		}))]));
+		
+		Ok(options)
+	}
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum ValueBase {
@@ -167,4 +335,69 @@ impl<T: OptionValue> OptionOrStaticBase<T> {
 			OptionOrStaticBase::Static(v) => OptionOrStatic::Static(v),
 		}
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn jobs(jobs: &str) -> OptionValueJobsBase {
+		serde_yaml::from_str(&format!("
+roles:
+  - {{name: Tank, color: [0.0, 0.0, 1.0], option: Tank Role Color}}
+  - {{name: Melee, color: [1.0, 0.0, 0.0], basic: Dps Color}}
+  - {{name: Ranged, color: [1.0, 1.0, 0.0], basic: Dps Color}}
+jobs:
+{jobs}")).unwrap()
+	}
+	
+	#[test]
+	fn job_options() {
+		let jobs = jobs("
+  - {abbreviation: pld, role: Tank, icon: 3, color: Pld Color}
+  - {abbreviation: gla, job: pld, icon: 1}
+  - {abbreviation: mnk, role: Melee, icon: 4, color: Mnk Color}
+  - {abbreviation: brd, role: Ranged, icon: 2, color: Brd Color}");
+		
+		assert_eq!(jobs.icon_color(1), Some("Pld Color"));
+		assert_eq!(jobs.icon_color(2), Some("Brd Color"));
+		assert_eq!(jobs.icon_color(5), None);
+		assert_eq!(jobs.role_color("Tank"), Some("Tank Role Color"));
+		assert_eq!(jobs.role_color("Melee"), None);
+		
+		let options = jobs.options("Job Colors").unwrap();
+		let names = options.iter().map(|v| v.keys().next().unwrap().as_str()).collect::<Vec<_>>();
+		assert_eq!(names, ["Tank Role Color", "Pld Color", "Mnk Color", "Brd Color", "Job Colors"]);
+		let OptionBase::Color(color) = &options[2]["Mnk Color"] else {panic!("Mnk Color isn't a color option")};
+		assert_eq!(color.default, [1.0, 0.0, 0.0]);
+		
+		let OptionBase::Grouped(grouped) = &options[4]["Job Colors"] else {panic!("Job Colors isn't a grouped option")};
+		let grouping = |name: &str| grouped.options.iter().find_map(|v| v.get(name)).unwrap().iter().map(|v| {
+			let (name, value) = v.iter().next().unwrap();
+			(name.as_str(), match value {GroupedTypeBase::Option(v) => v.clone(), GroupedTypeBase::Category(_) => Vec::new()})
+		}).collect::<Vec<_>>();
+		
+		assert_eq!(grouping("Merged"), [("All", vec!["Tank Role Color".to_string(), "Pld Color".to_string(), "Mnk Color".to_string(), "Brd Color".to_string()])]);
+		assert_eq!(grouping("Basic"), [("Tank Color", vec!["Tank Role Color".to_string(), "Pld Color".to_string()]), ("Dps Color", vec!["Mnk Color".to_string(), "Brd Color".to_string()])]);
+		assert_eq!(grouping("Grouped").len(), 3);
+		assert_eq!(grouping("Individual").iter().map(|(v, _)| *v).collect::<Vec<_>>(), ["Tank", "Tank Role Color", "Pld Color", "Melee", "Mnk Color", "Ranged", "Brd Color"]);
+	}
+	
+	#[test]
+	fn job_errors() {
+		assert!(jobs("  - {abbreviation: gla, job: pld, icon: 1}").options("Job Colors").is_err());
+		assert!(jobs("  - {abbreviation: pld, role: Healer, icon: 1, color: Pld Color}").options("Job Colors").is_err());
+		assert!(jobs("
+  - {abbreviation: pld, role: Tank, icon: 1, color: Pld Color}
+  - {abbreviation: war, role: Tank, icon: 1, color: War Color}").options("Job Colors").is_err());
+	}
+	
+	// the icons stage goes through the job icons until one is missing, so every icon needs a job
+	#[test]
+	fn meta_jobs() {
+		let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+		let jobs = MetaBase::load(&root.join("meta.yaml")).unwrap().jobs.unwrap();
+		let mut icons = jobs.jobs.iter().map(|v| v.icon).collect::<Vec<_>>();
+		icons.sort();
+		assert_eq!(icons, (1..=icons.len()).collect::<Vec<_>>());
+	}
+}
@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use crate::tex::MAX_LEVELS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+	// averages every 2x2 block, cheap and doesn't ring
+	Box,
+	// sharper, but can ring around hard edges
+	Lanczos,
+	// box filter on premultiplied colors, so fully transparent pixels don't bleed their (usually black) color into the edges
+	Premultiplied,
+}
+
+// every level after the first one, down to 1x1
+pub fn chain(width: u32, height: u32, rgba: &[u8], filter: Filter) -> Vec<(u32, u32, Vec<u8>)> {
+	let mut levels: Vec<(u32, u32, Vec<u8>)> = Vec::new();
+	let (mut w, mut h) = (width, height);
+	while (w > 1 || h > 1) && levels.len() + 1 < MAX_LEVELS {
+		let (pw, ph, previous) = levels.last().map_or((w, h, rgba), |(w, h, v)| (*w, *h, v.as_slice()));
+		w = (w / 2).max(1);
+		h = (h / 2).max(1);
+		let next = match filter {
+			Filter::Box => downsample_box(pw, ph, previous, false),
+			Filter::Premultiplied => downsample_box(pw, ph, previous, true),
+			// resampling from the previous level instead of the full image keeps it fast, lanczos holds up fine doing so
+			Filter::Lanczos => {
+				let img = image::RgbaImage::from_raw(pw, ph, previous.to_vec()).expect("level has the wrong size");
+				image::imageops::resize(&img, w, h, image::imageops::FilterType::Lanczos3).into_raw()
+			}
+		};
+		
+		levels.push((w, h, next));
+	}
+	
+	levels
+}
+
+// averages every factor x factor block, for sizes that are a multiple of the factor
+pub fn shrink(width: u32, height: u32, rgba: &[u8], factor: u32) -> Vec<u8> {
+	let (w, h) = (width / factor, height / factor);
+	let count = factor * factor;
+	let mut out = Vec::with_capacity((w * h * 4) as usize);
+	for y in 0..h {
+		for x in 0..w {
+			let mut sum = [0u32; 4];
+			for sy in y * factor..(y + 1) * factor {
+				for sx in x * factor..(x + 1) * factor {
+					let i = ((sy * width + sx) * 4) as usize;
+					for c in 0..4 {
+						sum[c] += rgba[i + c] as u32;
+					}
+				}
+			}
+			
+			out.extend(sum.map(|v| ((v + count / 2) / count) as u8));
+		}
+	}
+	
+	out
+}
+
+fn downsample_box(width: u32, height: u32, rgba: &[u8], premultiplied: bool) -> Vec<u8> {
+	let (w, h) = ((width / 2).max(1), (height / 2).max(1));
+	let mut out = Vec::with_capacity((w * h * 4) as usize);
+	for y in 0..h {
+		for x in 0..w {
+			// odd sizes fold the last row/column into the one before it
+			let xs = if x == w - 1 {x * 2..width} else {x * 2..x * 2 + 2};
+			let ys = if y == h - 1 {y * 2..height} else {y * 2..y * 2 + 2};
+			let mut sum = [0.0f32; 4];
+			let mut count = 0.0;
+			for sy in ys {
+				for sx in xs.clone() {
+					let i = ((sy * width + sx) * 4) as usize;
+					let a = rgba[i + 3] as f32;
+					let m = if premultiplied {a / 255.0} else {1.0};
+					sum[0] += rgba[i] as f32 * m;
+					sum[1] += rgba[i + 1] as f32 * m;
+					sum[2] += rgba[i + 2] as f32 * m;
+					sum[3] += a;
+					count += 1.0;
+				}
+			}
+			
+			let a = sum[3] / count;
+			let m = if premultiplied {if a > 0.0 {255.0 / a} else {0.0}} else {1.0};
+			for v in &sum[..3] {
+				out.push((v / count * m).round().clamp(0.0, 255.0) as u8);
+			}
+			out.push(a.round() as u8);
+		}
+	}
+	
+	out
+}
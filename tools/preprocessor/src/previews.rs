@@ -0,0 +1,153 @@
+use std::{collections::HashMap, path::Path};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use crate::{composite, compression, metabase, SvgResult, diagnostics::{Diagnostic, Diagnostics}};
+
+// the image a markdown description shows, window/rounded.png for "[md]![Image](window/rounded.png)"
+fn image_path(description: &str) -> Option<&str> {
+	let image = &description[description.find("![")?..];
+	let start = image.find("](")? + 2;
+	let end = start + image[start..].find(")")?;
+	Some(&image[start..end])
+}
+
+// renders the option previews the descriptions in meta.yaml reference, from the svgs of every option with its default colors
+pub fn previews(svg_root: &Path, lib: &crate::svg::Library, meta_path: &Path, assets_root: &Path, tex: &compression::Rules, diag: &Diagnostics) -> Result<(), crate::Error> {
+	let source = meta_path.to_string_lossy();
+	let meta = metabase::MetaBase::load(meta_path)?;
+	
+	let mut colors = HashMap::new();
+	let mut previews = Vec::new();
+	for (name, value) in meta.options.iter().flatten() {
+		match value {
+			metabase::OptionBase::Color(value) => {colors.insert(name.as_str(), value.color(&value.default));}
+			metabase::OptionBase::Files(value) => {
+				let subs = value.options.iter().flatten().map(|(k, v)| (k.as_str(), v)).collect::<HashMap<_, _>>();
+				for (sub, sub_value) in &subs {
+					let Some(image) = image_path(&sub_value.description) else {continue};
+					// the sub options it inherits files from, itself first
+					let mut chain = vec![*sub];
+					while let Some(inherit) = subs.get(chain.last().unwrap()).and_then(|v| v.inherit.as_deref()) {
+						if chain.contains(&inherit) {break}
+						chain.push(inherit);
+					}
+					
+					previews.push((name.as_str(), chain, sub_value.preview.as_deref(), image));
+				}
+			}
+			_ => {}
+		}
+	}
+	
+	let mut options = HashMap::<(String, String), Vec<SvgResult>>::new();
+	for svg in crate::get_svgs(svg_root)?.into_par_iter().filter_map(|file| {
+		let result = std::fs::read_to_string(&file)
+			.map_err(|e| e.into())
+			.and_then(|data| crate::split_svgs(&data, &file, lib));
+		
+		match result {
+			Ok(svgs) => Some(svgs),
+			Err(e) => {
+				diag.push_error(file.to_string_lossy(), e);
+				None
+			}
+		}
+	}).flatten().collect::<Vec<_>>() {
+		if let Some(option) = svg.option.clone() {
+			options.entry(option).or_default().push(svg);
+		}
+	}
+	
+	let font = crate::load_fonts();
+	let created = previews.par_iter().filter(|(option, chain, preview, asset)| {
+		let warn = |message: String| diag.push(Diagnostic::warning(message).source(source.clone()).context("option", format!("{option}:{}", chain[0])));
+		
+		let Some(svgs) = chain.iter().find_map(|sub| options.get(&(option.to_string(), sub.to_string()))) else {
+			warn(format!("No svg provides the option, {asset} can't be created"));
+			return false;
+		};
+		
+		let result = match preview {
+			Some(preview) => match svgs.iter().find(|v| v.path == *preview) {
+				Some(svg) => render(svg, &colors, tex, &font),
+				None => {
+					warn(format!("The option has no preview texture {preview}"));
+					return false;
+				}
+			}
+			
+			None => largest(svgs, &font).and_then(|svg| render(svg, &colors, tex, &font)),
+		};
+		
+		match result.and_then(|(width, height, data)| {
+			let path = assets_root.join(asset);
+			_ = std::fs::create_dir_all(path.parent().unwrap());
+			image::RgbaImage::from_raw(width, height, data).ok_or("Pixels don't match the image size")?.save(path)?;
+			Ok(())
+		}) {
+			Ok(_) => true,
+			Err(e) => {
+				diag.push_error(source.clone(), e);
+				false
+			}
+		}
+	}).count();
+	
+	println!("Created {created} of {} previews", previews.len());
+	
+	Ok(())
+}
+
+// the texture that shows the most of the option
+fn largest<'a>(svgs: &'a [SvgResult], font: &resvg::usvg::fontdb::Database) -> Result<&'a SvgResult, crate::Error> {
+	let mut sizes = Vec::new();
+	for svg in svgs {
+		let tree = resvg::usvg::Tree::from_str(&svg.layers[0].1, &resvg::usvg::Options::default(), font)?;
+		sizes.push((tree.size().width() * tree.size().height(), svg));
+	}
+	
+	// the path breaks ties so the same texture gets picked every time
+	sizes.into_iter()
+		.max_by(|a, b| a.0.total_cmp(&b.0).then_with(|| b.1.path.cmp(&a.1.path)))
+		.map(|v| v.1)
+		.ok_or("Option has no textures".into())
+}
+
+// the composite of every layer with the default colors of their color options
+fn render(svg: &SvgResult, colors: &HashMap<&str, Vec<f32>>, tex: &compression::Rules, font: &resvg::usvg::fontdb::Database) -> Result<(u32, u32, Vec<u8>), crate::Error> {
+	let mut size = (0, 0);
+	let mut layers = Vec::new();
+	let mut masks = Vec::new();
+	for (style, layer) in &svg.layers {
+		let tree = resvg::usvg::Tree::from_str(layer, &resvg::usvg::Options::default(), font)?;
+		let (width, height, data) = crate::rasterize(&tree, 1.0, tex.supersample(&svg.path))?;
+		size = (width, height);
+		let factor = |v: &crate::Factor| match v {
+			crate::Factor::Static(v) => *v,
+			crate::Factor::Option(v) => colors.get(v.as_str()).and_then(|v| composite::factor(v)).unwrap_or(1.0),
+		};
+		
+		if let Some(cull_point) = &style.mask {
+			masks.push((crate::luminance_mask(&data), factor(cull_point)));
+			continue;
+		}
+		
+		let rgba = |option: &str| colors.get(option).map_or([1.0; 4], |v| composite::rgba(v));
+		let mut color = style.color.as_deref().map_or([1.0; 4], rgba);
+		if let Some((to, position)) = &style.gradient {
+			let to = rgba(to);
+			color = std::array::from_fn(|i| color[i] + (to[i] - color[i]) * position);
+		}
+		
+		let opacity = style.opacity.as_ref().map_or(1.0, factor);
+		layers.push((data, composite::multiply(color, [1.0, 1.0, 1.0, opacity]), style.blend));
+	}
+	
+	for (data, _, _) in &mut layers {
+		for (mask, cull_point) in &masks {
+			composite::mask(data, mask, *cull_point);
+		}
+	}
+	
+	let layers = layers.iter().map(|(rgba, color, blend)| composite::Layer{rgba, color: *color, blend: *blend}).collect::<Vec<_>>();
+	Ok((size.0, size.1, composite::flatten(size.0, size.1, &layers)))
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+use image::{Rgba, RgbaImage};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use crate::{flatten, manifest, metabase, diagnostics::{Diagnostic, Diagnostics}};
+
+// the textures that show off a preset the most, a row each
+const ROWS: [(&str, &[&str]); 6] = [
+	("Windows", &["ui/uld/bgparts_hr1.tex", "ui/uld/windowb_hr1.tex", "ui/uld/windowa_button_hr1.tex"]),
+	("Buttons", &["ui/uld/buttona_hr1.tex", "ui/uld/tabbuttona_hr1.tex", "ui/uld/togglebuttonswitch_hr1.tex"]),
+	("Gauges", &["ui/uld/parameter_gauge_hr1.tex", "ui/uld/partylist_gaugehp_hr1.tex", "ui/uld/exp_gauge_hr1.tex"]),
+	("Minimap", &["ui/uld/navimap_hr1.tex"]),
+	("Job Icons", &["ui/icon/062000/062119_hr1.tex", "ui/icon/062000/062121_hr1.tex", "ui/icon/062000/062124_hr1.tex"]),
+	("Checkboxes", &["ui/uld/checkboxa_hr1.tex", "ui/uld/radiobuttona_hr1.tex"]),
+];
+// textures larger than this get scaled down to fit
+const MAX_CELL: (u32, u32) = (512, 256);
+const PADDING: u32 = 16;
+const LABEL_WIDTH: u32 = 200;
+const TITLE_SIZE: f32 = 40.0;
+const LABEL_SIZE: f32 = 24.0;
+// somewhere between light and dark so both kinds of themes stand out
+const BACKGROUND: Rgba<u8> = Rgba([64, 64, 68, 255]);
+
+// file name of the sheet of a preset, Material UI > material_ui.png
+fn file_name(preset: &str) -> String {
+	format!("{}.png", preset.to_ascii_lowercase().split_whitespace().collect::<Vec<_>>().join("_"))
+}
+
+// renders a contact sheet of the key textures of every preset (or only the given one) from what previous builds created
+pub fn sheets(target_root: &Path, meta_path: &Path, preset: Option<&str>, out: &Path, game: &dyn crate::game::Source, diag: &Diagnostics) -> Result<(), crate::Error> {
+	let meta = metabase::MetaBase::load(meta_path)?;
+	let manifest = manifest::load(target_root)?;
+	let font = crate::load_fonts();
+	let presets = meta.presets.iter().flat_map(|v| v.keys())
+		.filter(|v| preset.is_none_or(|preset| preset == v.as_str()))
+		.collect::<Vec<_>>();
+	if let Some(preset) = preset.filter(|_| presets.is_empty()) {
+		return Err(format!("meta.yaml has no preset '{preset}'").into());
+	}
+	
+	_ = std::fs::create_dir_all(out);
+	let created = presets.par_iter().filter(|preset| {
+		match sheet(target_root, &meta, &manifest, preset, &font, game, diag).and_then(|img| Ok(img.save(out.join(file_name(preset)))?)) {
+			Ok(_) => true,
+			Err(e) => {
+				diag.push_error(format!("<sheet {preset}>"), e);
+				false
+			}
+		}
+	}).count();
+	
+	println!("Created {created} of {} contact sheets in {out:?}", presets.len());
+	
+	Ok(())
+}
+
+fn sheet(target_root: &Path, meta: &metabase::MetaBase, manifest: &manifest::Files, preset: &str, font: &resvg::usvg::fontdb::Database, game: &dyn crate::game::Source, diag: &Diagnostics) -> Result<RgbaImage, crate::Error> {
+	let values = flatten::Values::new(meta, preset)?;
+	let textures = flatten::textures(manifest, &values);
+	let files_root = target_root.join("files");
+	
+	let mut rows = Vec::new();
+	for (label, game_paths) in ROWS {
+		let mut cells = Vec::new();
+		for game_path in game_paths {
+			let result = textures.get(*game_path)
+				.ok_or_else(|| "The preset doesn't provide this texture, run the stage that creates it first".into())
+				.and_then(|mod_path| flatten::flatten_file(&files_root, game_path, mod_path, &values, game, diag));
+			
+			match result {
+				Ok((width, height, data)) => {
+					let img = RgbaImage::from_raw(width, height, data).ok_or("Pixels don't match the image size")?;
+					let scale = (MAX_CELL.0 as f32 / width as f32).min(MAX_CELL.1 as f32 / height as f32).min(1.0);
+					cells.push(if scale < 1.0 {
+						image::imageops::resize(&img, ((width as f32 * scale) as u32).max(1), ((height as f32 * scale) as u32).max(1), image::imageops::FilterType::Triangle)
+					} else {
+						img
+					});
+				}
+				
+				Err(e) => diag.push(Diagnostic::warning(format!("Left out of the contact sheet: {e}")).source(*game_path).context("preset", preset)),
+			}
+		}
+		
+		if !cells.is_empty() {
+			rows.push((label, cells));
+		}
+	}
+	
+	let title = text(preset, TITLE_SIZE, font)?;
+	let row_width = |cells: &[RgbaImage]| LABEL_WIDTH + cells.iter().map(|v| v.width() + PADDING).sum::<u32>();
+	let width = rows.iter().map(|(_, cells)| row_width(cells)).max().unwrap_or(0).max(title.width()) + PADDING * 2;
+	let height = PADDING * 2 + title.height() + rows.iter().map(|(_, cells)| cells.iter().map(|v| v.height()).max().unwrap_or(0) + PADDING).sum::<u32>();
+	
+	let mut img = RgbaImage::from_pixel(width, height, BACKGROUND);
+	image::imageops::overlay(&mut img, &title, PADDING as i64, PADDING as i64);
+	let mut y = PADDING * 2 + title.height();
+	for (label, cells) in &rows {
+		let row_height = cells.iter().map(|v| v.height()).max().unwrap_or(0);
+		let label = text(label, LABEL_SIZE, font)?;
+		image::imageops::overlay(&mut img, &label, PADDING as i64, (y + row_height.saturating_sub(label.height()) / 2) as i64);
+		
+		let mut x = PADDING + LABEL_WIDTH;
+		for cell in cells {
+			image::imageops::overlay(&mut img, cell, x as i64, (y + (row_height - cell.height()) / 2) as i64);
+			x += cell.width() + PADDING;
+		}
+		
+		y += row_height + PADDING;
+	}
+	
+	Ok(img)
+}
+
+// a single line of white text, rendered through an svg so it uses the same fonts as the textures
+fn text(text: &str, size: f32, font: &resvg::usvg::fontdb::Database) -> Result<RgbaImage, crate::Error> {
+	let escaped = text.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;");
+	let (width, height) = ((text.chars().count() as f32 * size) as u32, (size * 1.4) as u32);
+	let svg = format!(r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}"><text x="0" y="{}" font-family="AXIS, sans-serif" font-size="{size}" fill="#ffffff">{escaped}</text></svg>"##, size * 1.1);
+	let tree = resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default(), font)?;
+	let (width, height, data) = crate::rasterize(&tree, 1.0, 1)?;
+	let img = RgbaImage::from_raw(width, height, data).ok_or("Pixels don't match the image size")?;
+	
+	// the svg is sized for the widest characters, trim it down to what the text uses
+	let used = (0..width).rev().find(|x| (0..height).any(|y| img.get_pixel(*x, y)[3] > 0)).map_or(1, |x| x + 1);
+	Ok(image::imageops::crop_imm(&img, 0, 0, used, height).to_image())
+}
@@ -0,0 +1,272 @@
+use std::{collections::{HashMap, HashSet}, io::Write, path::Path};
+
+// a parsed svg, kept as a tree so layers can be split out of it and references resolved from anywhere in it
+#[derive(Debug, Clone)]
+pub enum Node {
+	Element(Element),
+	Text(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Element {
+	pub name: xml::name::OwnedName,
+	pub namespace: xml::namespace::Namespace,
+	pub attributes: Vec<xml::attribute::OwnedAttribute>,
+	pub children: Vec<Node>,
+	// where it starts in the source svg
+	pub position: xml::common::TextPosition,
+}
+
+impl Element {
+	// the root element of the document
+	pub fn parse(data: &str, file: &Path) -> Result<Element, crate::Error> {
+		let mut xml_reader = xml::EventReader::from_str(data);
+		let mut stack = Vec::<Element>::new();
+		loop {
+			let e = xml_reader.next().map_err(|e| crate::Location{file, position: xml::common::Position::position(&e), ancestors: &[]}.error(format!("Invalid xml: {}", e.msg())))?;
+			let position = xml::common::Position::position(&xml_reader);
+			match e {
+				xml::reader::XmlEvent::StartElement{name, attributes, namespace} => stack.push(Element{name, namespace, attributes, children: Vec::new(), position}),
+				xml::reader::XmlEvent::EndElement{..} => {
+					let element = stack.pop().unwrap();
+					match stack.last_mut() {
+						Some(parent) => parent.children.push(Node::Element(element)),
+						None => return Ok(element),
+					}
+				}
+				
+				xml::reader::XmlEvent::Characters(v) | xml::reader::XmlEvent::CData(v) => {
+					if let Some(parent) = stack.last_mut() {
+						parent.children.push(Node::Text(v));
+					}
+				}
+				
+				xml::reader::XmlEvent::EndDocument => return Err("Svg has no root element".into()),
+				_ => {}
+			}
+		}
+	}
+	
+	pub fn attribute(&self, local_name: &str) -> Option<&str> {
+		self.attributes.iter().find(|v| v.name.local_name == local_name).map(|v| v.value.as_str())
+	}
+	
+	// the inkscape layer name
+	pub fn label(&self) -> Option<&str> {
+		self.attribute("label")
+	}
+	
+	pub fn id(&self) -> Option<&str> {
+		self.attribute("id")
+	}
+	
+	pub fn is(&self, local_name: &str) -> bool {
+		self.name.local_name == local_name
+	}
+	
+	// layers starting with _ are never rendered and ones starting with + only where they're used
+	pub fn is_skipped(&self) -> bool {
+		self.label().is_some_and(|v| v.starts_with('_') || v.starts_with('+'))
+	}
+	
+	pub fn is_reusable(&self) -> bool {
+		self.label().is_some_and(|v| v.starts_with('+'))
+	}
+	
+	pub fn elements(&self) -> impl Iterator<Item = &Element> {
+		self.children.iter().filter_map(|v| match v {
+			Node::Element(v) => Some(v),
+			Node::Text(_) => None,
+		})
+	}
+	
+	// itself and every element below it
+	pub fn descendants(&self) -> Vec<&Element> {
+		let mut elements = vec![self];
+		let mut i = 0;
+		while i < elements.len() {
+			elements.extend(elements[i].elements());
+			i += 1;
+		}
+		
+		elements
+	}
+	
+	// every element with an id, the first one wins if they're used more than once
+	pub fn ids(&self) -> HashMap<&str, &Element> {
+		let mut ids = HashMap::new();
+		for element in self.descendants() {
+			if let Some(id) = element.id() {
+				ids.entry(id).or_insert(element);
+			}
+		}
+		
+		ids
+	}
+	
+	// ids of everything the element or its children refer to through url(#id) (quoted or not) or href, in the order they show up
+	pub fn references(&self) -> Vec<&str> {
+		let mut references = Vec::new();
+		for element in self.descendants() {
+			for attribute in &element.attributes {
+				if attribute.name.local_name == "href" {
+					if let Some(id) = attribute.value.strip_prefix('#') {
+						references.push(id);
+					}
+				}
+				
+				references.extend(url_ids(&attribute.value).into_iter().map(|v| &attribute.value[v]));
+			}
+		}
+		
+		references
+	}
+	
+	// a copy with different attributes and children, for the groups the layers of a split svg are wrapped in
+	pub fn with(&self, attributes: Vec<xml::attribute::OwnedAttribute>, children: Vec<Node>) -> Element {
+		Element {
+			name: self.name.clone(),
+			namespace: self.namespace.clone(),
+			attributes,
+			children,
+			position: self.position,
+		}
+	}
+	
+	pub fn renamed(mut self, local_name: &str) -> Element {
+		self.name.local_name = local_name.to_owned();
+		self
+	}
+	
+	fn for_each_mut(&mut self, f: &mut impl FnMut(&mut Element)) {
+		f(self);
+		for child in &mut self.children {
+			if let Node::Element(v) = child {
+				v.for_each_mut(f);
+			}
+		}
+	}
+	
+	// prefixes every id and the references to them
+	pub fn prefix_ids(&mut self, prefix: &str) {
+		let ids = self.ids().into_keys().map(|v| v.to_owned()).collect::<HashSet<_>>();
+		let prefixed = |id: &str| if ids.contains(id) {format!("{prefix}{id}")} else {id.to_owned()};
+		self.for_each_mut(&mut |element| {
+			for attribute in &mut element.attributes {
+				if attribute.name.local_name == "id" {
+					attribute.value = prefixed(&attribute.value);
+					continue;
+				}
+				
+				if attribute.name.local_name == "href" {
+					if let Some(id) = attribute.value.strip_prefix('#') {
+						attribute.value = format!("#{}", prefixed(id));
+					}
+					continue;
+				}
+				
+				let mut value = String::new();
+				let mut end = 0;
+				for id in url_ids(&attribute.value) {
+					value.push_str(&attribute.value[end..id.start]);
+					value.push_str(&prefixed(&attribute.value[id.clone()]));
+					end = id.end;
+				}
+				value.push_str(&attribute.value[end..]);
+				attribute.value = value;
+			}
+		});
+	}
+	
+	pub fn write<W: Write>(&self, writer: &mut xml::EventWriter<W>) -> Result<(), xml::writer::Error> {
+		writer.write(xml::writer::XmlEvent::StartElement {
+			name: self.name.borrow(),
+			namespace: self.namespace.borrow(),
+			attributes: self.attributes.iter().map(|v| v.borrow()).collect(),
+		})?;
+		
+		for child in &self.children {
+			match child {
+				Node::Element(v) => v.write(writer)?,
+				Node::Text(v) => writer.write(xml::writer::XmlEvent::Characters(v))?,
+			}
+		}
+		
+		writer.write(xml::writer::XmlEvent::EndElement{name: Some(self.name.borrow())})
+	}
+}
+
+// where the ids are in the url(#id), url('#id') and url("#id") of an attribute value
+fn url_ids(value: &str) -> Vec<std::ops::Range<usize>> {
+	let mut ids = Vec::new();
+	let mut offset = 0;
+	while let Some(start) = value[offset..].find("url(") {
+		let start = offset + start + 4;
+		let Some(end) = value[start..].find(')').map(|v| start + v) else {break};
+		let inner = value[start..end].trim().trim_matches(|c| c == '"' || c == '\'');
+		if let Some(id) = inner.strip_prefix('#') {
+			let id_start = id.as_ptr() as usize - value.as_ptr() as usize;
+			ids.push(id_start..id_start + id.len());
+		}
+		offset = end;
+	}
+	
+	ids
+}
+
+// ids of every element in the trees
+pub fn defined<'a>(elements: impl IntoIterator<Item = &'a Element>) -> HashSet<&'a str> {
+	elements.into_iter().flat_map(|v| v.descendants()).filter_map(|v| v.id()).collect()
+}
+
+// svgs shared between vectors, a <use> refers to them by their path in the library without extension (lib:window/corner_rounded)
+// or to an element in them by id (lib:window/corner_rounded#shadow)
+#[derive(Debug, Default)]
+pub struct Library {
+	parts: HashMap<String, Element>,
+}
+
+impl Library {
+	// empty if the directory doesn't exist
+	pub fn load(root: &Path) -> Result<Library, crate::Error> {
+		let mut parts = HashMap::new();
+		if !root.exists() {return Ok(Library::default())}
+		
+		for file in crate::get_svgs(root)? {
+			let name = file.strip_prefix(root)?.with_extension("").to_string_lossy().replace('\\', "/");
+			let mut part = Element::parse(&std::fs::read_to_string(&file)?, &file)?;
+			part.prefix_ids(&Self::prefix(&name));
+			parts.insert(name, part);
+		}
+		
+		Ok(Library{parts})
+	}
+	
+	// ids of a part are prefixed so they can't clash with the ones of the svg using it or other parts
+	fn prefix(name: &str) -> String {
+		format!("lib_{}_", name.replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+	}
+	
+	// the part a reference (without lib:) is in and the element it refers to, which is the part itself without an id
+	pub fn find(&self, reference: &str) -> Option<(&Element, &Element)> {
+		let (name, id) = match reference.split_once('#') {
+			Some((name, id)) => (name, Some(id)),
+			None => (reference, None),
+		};
+		
+		let part = self.parts.get(name)?;
+		match id {
+			Some(id) => {
+				let id = format!("{}{id}", Self::prefix(name));
+				Some((part, part.descendants().into_iter().find(|v| v.id() == Some(id.as_str()))?))
+			}
+			
+			None => Some((part, part)),
+		}
+	}
+	
+	// every element with an id in every part
+	pub fn ids(&self) -> HashMap<&str, &Element> {
+		self.parts.values().flat_map(|v| v.ids()).collect()
+	}
+}
@@ -0,0 +1,336 @@
+use std::io::{Read, Write};
+use serde::Deserialize;
+use crate::{bc, compression::Settings, mips};
+
+// the header is followed directly by the surfaces
+pub const HEADER_SIZE: usize = 80;
+// the header has room for 13 surfaces
+pub const MAX_LEVELS: usize = 13;
+// a plain 2d texture
+pub const ATTRIBUTES_2D: u32 = 0x00800000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+	// uncompressed B8G8R8A8
+	#[default]
+	Bgra8,
+	Bc1,
+	Bc3,
+	Bc7,
+}
+
+impl Format {
+	// the format id in the tex header
+	pub fn id(self) -> u32 {
+		match self {
+			Format::Bgra8 => 0x1450,
+			Format::Bc1 => 0x3420,
+			Format::Bc3 => 0x3431,
+			Format::Bc7 => 0x6432,
+		}
+	}
+	
+	pub fn from_id(id: u32) -> Option<Self> {
+		[Format::Bgra8, Format::Bc1, Format::Bc3, Format::Bc7].into_iter().find(|v| v.id() == id)
+	}
+	
+	pub fn block(self) -> Option<bc::Block> {
+		match self {
+			Format::Bgra8 => None,
+			Format::Bc1 => Some(bc::Block::Bc1),
+			Format::Bc3 => Some(bc::Block::Bc3),
+			Format::Bc7 => Some(bc::Block::Bc7),
+		}
+	}
+	
+	// bytes a single surface of the given size takes up
+	pub fn surface_size(self, width: u32, height: u32) -> usize {
+		match self.block() {
+			Some(block) => width.div_ceil(4) as usize * height.div_ceil(4) as usize * block.size(),
+			None => width as usize * height as usize * 4,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+	pub attributes: u32,
+	pub format: Format,
+	pub width: u16,
+	pub height: u16,
+	pub depth: u16,
+	pub mip_levels: u8,
+	pub array_size: u8,
+	pub lod_offsets: [u32; 3],
+	// where every surface starts from the start of the file, unused ones are 0
+	pub mip_offsets: [u32; MAX_LEVELS],
+}
+
+impl Header {
+	// header of a 2d texture with the given surfaces directly after it
+	pub fn new(format: Format, width: u16, height: u16, surface_sizes: &[usize]) -> Self {
+		let mut mip_offsets = [0; MAX_LEVELS];
+		let mut offset = HEADER_SIZE;
+		for (i, size) in surface_sizes.iter().enumerate() {
+			mip_offsets[i] = offset as u32;
+			offset += size;
+		}
+		
+		Self {
+			attributes: ATTRIBUTES_2D,
+			format,
+			width,
+			height,
+			depth: 0,
+			mip_levels: surface_sizes.len() as u8,
+			array_size: 0,
+			lod_offsets: [0, 1, 2],
+			mip_offsets,
+		}
+	}
+	
+	pub fn read(reader: &mut impl Read) -> Result<Self, crate::Error> {
+		let mut data = [0u8; HEADER_SIZE];
+		reader.read_exact(&mut data)?;
+		let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+		let u32_at = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+		
+		let format = u32_at(4);
+		let header = Self {
+			attributes: u32_at(0),
+			format: Format::from_id(format).ok_or_else(|| format!("Unsupported tex format 0x{format:04X}"))?,
+			width: u16_at(8),
+			height: u16_at(10),
+			depth: u16_at(12),
+			mip_levels: data[14],
+			array_size: data[15],
+			lod_offsets: std::array::from_fn(|i| u32_at(16 + i * 4)),
+			mip_offsets: std::array::from_fn(|i| u32_at(28 + i * 4)),
+		};
+		
+		if header.mip_levels == 0 || header.mip_levels as usize > MAX_LEVELS {
+			return Err(format!("Invalid tex mip count {}", header.mip_levels).into());
+		}
+		
+		Ok(header)
+	}
+	
+	pub fn write(&self, writer: &mut impl Write) -> Result<(), crate::Error> {
+		writer.write_all(&self.attributes.to_le_bytes())?;
+		writer.write_all(&self.format.id().to_le_bytes())?;
+		writer.write_all(&self.width.to_le_bytes())?;
+		writer.write_all(&self.height.to_le_bytes())?;
+		writer.write_all(&self.depth.to_le_bytes())?;
+		writer.write_all(&[self.mip_levels, self.array_size])?;
+		for v in self.lod_offsets.iter().chain(&self.mip_offsets) {
+			writer.write_all(&v.to_le_bytes())?;
+		}
+		
+		Ok(())
+	}
+	
+	// size of a mip level, never smaller than 1x1
+	pub fn level_size(&self, level: usize) -> (u32, u32) {
+		(((self.width as u32) >> level).max(1), ((self.height as u32) >> level).max(1))
+	}
+}
+
+pub struct Tex {
+	pub header: Header,
+	// every mip level in the format of the header, largest first
+	pub surfaces: Vec<Vec<u8>>,
+}
+
+impl Tex {
+	// encodes rgba8 pixels, with a mip chain if the settings ask for one
+	pub fn new(width: u16, height: u16, rgba: &[u8], settings: Settings) -> Self {
+		let mut surfaces = vec![encode_surface(width as u32, height as u32, rgba, settings)];
+		if let Some(filter) = settings.mips {
+			for (w, h, level) in mips::chain(width as u32, height as u32, rgba, filter) {
+				surfaces.push(encode_surface(w, h, &level, settings));
+			}
+		}
+		
+		Self {
+			header: Header::new(settings.format, width, height, &surfaces.iter().map(|v| v.len()).collect::<Vec<_>>()),
+			surfaces,
+		}
+	}
+	
+	pub fn read(reader: &mut impl Read) -> Result<Self, crate::Error> {
+		let header = Header::read(reader)?;
+		let mut data = Vec::new();
+		reader.read_to_end(&mut data)?;
+		
+		let mut surfaces = Vec::with_capacity(header.mip_levels as usize);
+		for level in 0..header.mip_levels as usize {
+			let (w, h) = header.level_size(level);
+			let start = (header.mip_offsets[level] as usize).checked_sub(HEADER_SIZE).ok_or_else(|| format!("Mip {level} starts inside of the header"))?;
+			let end = start + header.format.surface_size(w, h);
+			let surface = data.get(start..end).ok_or_else(|| format!("Mip {level} goes past the end of the file"))?;
+			surfaces.push(surface.to_vec());
+		}
+		
+		Ok(Self{header, surfaces})
+	}
+	
+	pub fn write(&self, writer: &mut impl Write) -> Result<(), crate::Error> {
+		self.header.write(writer)?;
+		for surface in &self.surfaces {
+			writer.write_all(surface)?;
+		}
+		
+		Ok(())
+	}
+	
+	// rgba8 pixels of a mip level
+	pub fn decode(&self, level: usize) -> Result<Vec<u8>, crate::Error> {
+		let surface = self.surfaces.get(level).ok_or_else(|| format!("Tex has no mip {level}"))?;
+		let (w, h) = self.header.level_size(level);
+		match self.header.format.block() {
+			Some(block) => bc::decode(block, w as usize, h as usize, surface),
+			None => Ok(surface.chunks_exact(4).flat_map(|v| [v[2], v[1], v[0], v[3]]).collect()),
+		}
+	}
+}
+
+fn encode_surface(width: u32, height: u32, rgba: &[u8], settings: Settings) -> Vec<u8> {
+	if let Some(block) = settings.format.block() {
+		bc::encode(block, settings.quality, width as usize, height as usize, rgba)
+	} else {
+		rgba.chunks_exact(4).flat_map(|v| [v[2], v[1], v[0], v[3]]).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	// smooth gradients with a couple hard edges, like most of our ui
+	fn image(width: u32, height: u32, alpha: bool) -> Vec<u8> {
+		let mut rgba = Vec::new();
+		for y in 0..height {
+			for x in 0..width {
+				let edge = if x > width / 2 && y > height / 2 {96} else {0};
+				rgba.extend([
+					(x * 255 / width) as u8,
+					(y * 255 / height) as u8,
+					(128 + edge) as u8,
+					if alpha {((x + y) * 255 / (width + height)) as u8} else {255},
+				]);
+			}
+		}
+		
+		rgba
+	}
+	
+	fn round_trip(width: u16, height: u16, rgba: &[u8], settings: Settings) -> Tex {
+		let mut data = Vec::new();
+		Tex::new(width, height, rgba, settings).write(&mut data).unwrap();
+		assert_eq!(data.len(), HEADER_SIZE + (0..data[14] as usize).map(|i| {
+			let w = (width as u32 >> i).max(1);
+			let h = (height as u32 >> i).max(1);
+			settings.format.surface_size(w, h)
+		}).sum::<usize>());
+		
+		Tex::read(&mut data.as_slice()).unwrap()
+	}
+	
+	fn mean_error(a: &[u8], b: &[u8]) -> f32 {
+		assert_eq!(a.len(), b.len());
+		a.iter().zip(b).map(|(a, b)| (*a as f32 - *b as f32).abs()).sum::<f32>() / a.len() as f32
+	}
+	
+	#[test]
+	fn header_round_trip() {
+		let header = Header {
+			attributes: ATTRIBUTES_2D,
+			format: Format::Bc3,
+			width: 1568,
+			height: 256,
+			depth: 1,
+			mip_levels: 3,
+			array_size: 0,
+			lod_offsets: [0, 1, 2],
+			mip_offsets: [80, 401488, 501840, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+		};
+		
+		let mut data = Vec::new();
+		header.write(&mut data).unwrap();
+		assert_eq!(data.len(), HEADER_SIZE);
+		assert_eq!(&data[4..8], &0x3431u32.to_le_bytes());
+		assert_eq!(Header::read(&mut data.as_slice()).unwrap(), header);
+	}
+	
+	#[test]
+	fn unsupported_format() {
+		let mut data = Vec::new();
+		Header::new(Format::Bgra8, 4, 4, &[64]).write(&mut data).unwrap();
+		data[4..8].copy_from_slice(&0x1130u32.to_le_bytes());
+		assert!(Header::read(&mut data.as_slice()).is_err());
+	}
+	
+	#[test]
+	fn bgra8_is_lossless() {
+		let rgba = image(37, 21, true);
+		let tex = round_trip(37, 21, &rgba, Settings::default());
+		assert_eq!(tex.header.format, Format::Bgra8);
+		assert_eq!((tex.header.width, tex.header.height, tex.header.mip_levels), (37, 21, 1));
+		assert_eq!(tex.header.mip_offsets[0], HEADER_SIZE as u32);
+		assert!(tex.header.mip_offsets[1..].iter().all(|v| *v == 0));
+		assert_eq!(tex.decode(0).unwrap(), rgba);
+	}
+	
+	#[test]
+	fn mip_chain() {
+		let rgba = image(64, 24, true);
+		let settings = Settings{mips: Some(mips::Filter::Box), ..Default::default()};
+		let tex = round_trip(64, 24, &rgba, settings);
+		// 64x24 down to 1x1
+		assert_eq!(tex.header.mip_levels, 7);
+		assert_eq!(tex.header.level_size(6), (1, 1));
+		for level in 1..7 {
+			let (w, h) = tex.header.level_size(level - 1);
+			assert_eq!(tex.header.mip_offsets[level], tex.header.mip_offsets[level - 1] + w * h * 4);
+		}
+		
+		let chain = mips::chain(64, 24, &rgba, mips::Filter::Box);
+		for (level, (w, h, expected)) in chain.iter().enumerate() {
+			assert_eq!(tex.header.level_size(level + 1), (*w, *h));
+			assert_eq!(&tex.decode(level + 1).unwrap(), expected);
+		}
+	}
+	
+	#[test]
+	fn compressed_round_trip() {
+		for (format, alpha, tolerance) in [(Format::Bc1, false, 6.0), (Format::Bc3, true, 6.0), (Format::Bc7, true, 4.0)] {
+			// not a multiple of 4 so the edge blocks get tested as well
+			let rgba = image(30, 18, alpha);
+			let settings = Settings{format, mips: Some(mips::Filter::Premultiplied), ..Default::default()};
+			let tex = round_trip(30, 18, &rgba, settings);
+			assert_eq!(tex.header.format, format);
+			assert_eq!(tex.surfaces[0].len(), 8 * 5 * format.block().unwrap().size());
+			
+			let error = mean_error(&tex.decode(0).unwrap(), &rgba);
+			assert!(error < tolerance, "{format:?} is off by {error} on average");
+			for level in 1..tex.header.mip_levels as usize {
+				tex.decode(level).unwrap();
+			}
+		}
+	}
+	
+	#[test]
+	fn bc1_punch_through() {
+		let mut rgba = image(8, 8, false);
+		for pixel in rgba.chunks_exact_mut(4).step_by(3) {
+			pixel[3] = 0;
+		}
+		
+		let tex = round_trip(8, 8, &rgba, Settings{format: Format::Bc1, ..Default::default()});
+		let decoded = tex.decode(0).unwrap();
+		for (a, b) in decoded.chunks_exact(4).zip(rgba.chunks_exact(4)) {
+			assert_eq!(a[3], b[3]);
+		}
+	}
+}
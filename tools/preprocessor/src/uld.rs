@@ -1,15 +1,14 @@
 use std::path::Path;
 use aetherment::noumenon::format::{external::Bytes, game::uld::*};
 
-pub fn ulds(target_root: &Path) -> Result<Vec<String>, crate::Error> {
+pub fn ulds(game: &dyn crate::game::Source, target_root: &Path) -> Result<Vec<String>, crate::Error> {
 	let files_root = target_root.join("files");
-	let n = aetherment::noumenon_instance().ok_or("Invalid Noumenon")?;
 	
 	let uld = |name, do_font, color: Option<&[&str]>| -> Result<String, crate::Error> {
 		let path = format!("ui/uld/{name}.uld");
 		println!("{path}");
 		
-		let mut u = n.file::<Uld>(&path)?;
+		let mut u = game.uld(&path)?;
 		if let Some(paths) = color {
 			for a in &mut u.assets {
 				// println!(" - {}", a.path);
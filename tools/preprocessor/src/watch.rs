@@ -0,0 +1,191 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use crate::{artifacts::Artifacts, cache, cli, compression, diagnostics::Diagnostics, manifest::{self, Files}};
+
+// we poll instead of relying on os file events, there's few enough files for it to not matter
+// and it avoids the mess of editors saving through temporary files
+fn mtimes(path: &Path, filter: &impl Fn(&Path) -> bool, times: &mut HashMap<PathBuf, SystemTime>) {
+	let Ok(dir) = std::fs::read_dir(path) else {return};
+	for entry in dir.flatten() {
+		let entry_path = entry.path();
+		if entry_path.is_dir() {
+			mtimes(&entry_path, filter, times);
+		} else if filter(&entry_path) {
+			if let Ok(time) = entry.metadata().and_then(|v| v.modified()) {
+				times.insert(entry_path, time);
+			}
+		}
+	}
+}
+
+fn is_svg(path: &Path) -> bool {
+	path.extension().map(|v| v.to_str()) == Some(Some("svg"))
+}
+
+fn update_meta(args: &cli::Args, diag: &Diagnostics) {
+	match manifest::load(&args.target).and_then(|files| crate::meta(&args.meta, &files, diag)) {
+		Ok(_) => println!("Updated meta.json"),
+		Err(e) => diag.push_error(args.meta.to_string_lossy(), e),
+	}
+}
+
+fn prune(files_root: &Path, path: &Path, old: Vec<(String, cache::Entry)>, new: &[(String, cache::Entry)], artifacts: &Artifacts) {
+	let old = cache::Cache{entries: old.into_iter().collect()};
+	let new = cache::Cache{entries: new.iter().cloned().collect()};
+	if let Err(e) = old.prune(files_root, &new, artifacts) {
+		println!("Failed pruning outputs of {path:?}: {e}");
+	}
+}
+
+// the stages a command runs that get rebuilt on changes
+pub struct Stages {
+	render: bool,
+	merge: bool,
+	meta: bool,
+}
+
+impl Stages {
+	// None for commands that don't build the mod out of the watched files
+	pub fn of(command: &cli::Command) -> Option<Self> {
+		Some(match command {
+			cli::Command::Render => Stages{render: true, merge: false, meta: false},
+			cli::Command::Merge => Stages{render: false, merge: true, meta: false},
+			cli::Command::Meta => Stages{render: false, merge: false, meta: true},
+			cli::Command::All => Stages{render: true, merge: true, meta: true},
+			_ => return None,
+		})
+	}
+}
+
+pub fn watch(args: &cli::Args, stages: Stages) -> Result<(), crate::Error> {
+	let target_root = args.target.as_path();
+	let files_root = target_root.join("files");
+	let font = crate::load_fonts();
+	let tex = compression::Rules::load(args)?;
+	let artifacts = Artifacts::new(args);
+	let mut lib = crate::svg::Library::load(&args.lib)?;
+	
+	// render everything once to know which file produced what, everything should be fresh in the cache so this is quick
+	// errors were already reported by the build before watching
+	let cache = cache::Cache::load(target_root);
+	let svgs = if stages.render {crate::get_svgs(&args.svg)?} else {Vec::new()};
+	let mut svgs = svgs.into_par_iter().filter_map(|path| {
+		let result = crate::render_file(&path, &files_root, &font, &lib, &cache, &tex, &artifacts).ok()?;
+		Some((path, result))
+	}).collect::<HashMap<_, _>>();
+	
+	let mut svg_times = HashMap::new();
+	let mut lib_times = HashMap::new();
+	if stages.render {
+		mtimes(&args.svg, &is_svg, &mut svg_times);
+		mtimes(&args.lib, &is_svg, &mut lib_times);
+	}
+	
+	let mut merge_times = HashMap::new();
+	if stages.merge {
+		mtimes(&args.merge, &|_| true, &mut merge_times);
+	}
+	
+	let mut meta_time = std::fs::metadata(&args.meta).and_then(|v| v.modified()).ok();
+	
+	println!("Watching for changes");
+	loop {
+		std::thread::sleep(Duration::from_millis(500));
+		let mut files_changed = false;
+		let diag = Diagnostics::default();
+		
+		// library, every svg could be using the part that changed, the cache skips the outputs that didn't change
+		let mut new_lib_times = HashMap::new();
+		if stages.render {
+			mtimes(&args.lib, &is_svg, &mut new_lib_times);
+		}
+		
+		if new_lib_times != lib_times {
+			println!("Reloading library {:?}", args.lib);
+			match crate::svg::Library::load(&args.lib) {
+				Ok(v) => {
+					lib = v;
+					svg_times.clear();
+				}
+				
+				Err(e) => diag.push_error(args.lib.to_string_lossy(), e),
+			}
+			
+			lib_times = new_lib_times;
+		}
+		
+		// svgs
+		let mut new_svg_times = HashMap::new();
+		if stages.render {
+			mtimes(&args.svg, &is_svg, &mut new_svg_times);
+		}
+		
+		if new_svg_times != svg_times {
+			let cache = cache::Cache{entries: svgs.values().flat_map(|(_, entries)| entries.clone()).collect()};
+			
+			for (path, time) in &new_svg_times {
+				if svg_times.get(path) == Some(time) {continue}
+				
+				println!("Rendering {path:?}");
+				match crate::render_file(path, &files_root, &font, &lib, &cache, &tex, &artifacts) {
+					Ok((files, entries)) => {
+						let (old_files, old_entries) = svgs.remove(path).unwrap_or_default();
+						prune(&files_root, path, old_entries, &entries, &artifacts);
+						
+						files_changed |= files != old_files;
+						svgs.insert(path.to_owned(), (files, entries));
+					}
+					
+					Err(e) => diag.push_error(path.to_string_lossy(), e),
+				}
+			}
+			
+			for path in svg_times.keys().filter(|v| !new_svg_times.contains_key(*v)) {
+				println!("Removing outputs of {path:?}");
+				if let Some((_, old_entries)) = svgs.remove(path) {
+					prune(&files_root, path, old_entries, &[], &artifacts);
+				}
+				
+				files_changed = true;
+			}
+			
+			svg_times = new_svg_times;
+			
+			let mut files = Files::new();
+			let mut cache = cache::Cache::default();
+			for (svg_files, entries) in svgs.values() {
+				manifest::merge(&mut files, svg_files.clone());
+				cache.entries.extend(entries.iter().cloned());
+			}
+			
+			if let Err(e) = cache.save(target_root).and_then(|_| manifest::save(target_root, "render", &files)) {
+				diag.push_error("<render stage>", e);
+			}
+		}
+		
+		// merge dir
+		let mut new_merge_times = HashMap::new();
+		if stages.merge {
+			mtimes(&args.merge, &|_| true, &mut new_merge_times);
+		}
+		
+		if new_merge_times != merge_times {
+			println!("Merging {:?}", args.merge);
+			match crate::merge(&args.merge, target_root).and_then(|files| manifest::save(target_root, "merge", &files)) {
+				Ok(_) => files_changed = true,
+				Err(e) => diag.push_error("<merge stage>", e),
+			}
+			
+			merge_times = new_merge_times;
+		}
+		
+		// meta
+		let new_meta_time = std::fs::metadata(&args.meta).and_then(|v| v.modified()).ok();
+		if stages.meta && (new_meta_time != meta_time || files_changed) {
+			update_meta(args, &diag);
+			meta_time = new_meta_time;
+		}
+		
+		diag.report();
+	}
+}